@@ -0,0 +1,133 @@
+//! rotating timestamped copies of the database, kept in a backups folder next to it
+
+use std::{fs, path::{Path, PathBuf}};
+
+use chrono::{Duration, Local, NaiveDateTime};
+
+use crate::util::{self, Database};
+
+const BACKUP_COUNT: usize = 10;
+/// saves closer together than this share one backup, so a busy session doesn't push out older ones
+const BACKUP_INTERVAL_MINUTES: i64 = 30;
+const STAMP_FORMAT: &str = "%Y%m%d-%H%M%S-%3f";
+
+/// one copy of the database in the backups folder
+pub struct Backup
+{
+    /// the backup file itself
+    pub path: PathBuf,
+    /// when it was taken, from the file name
+    pub date: NaiveDateTime
+}
+
+fn dir(db_path: &str) -> PathBuf
+{
+    Path::new(db_path).parent().unwrap_or(Path::new("")).join("backups")
+}
+
+// "cards.json" -> "cards", backups are named after the database they came from
+fn stem(db_path: &str) -> String
+{
+    Path::new(db_path).file_stem().unwrap_or_default().to_string_lossy().to_string()
+}
+
+/// copies the current database into the backups folder and drops the oldest ones past BACKUP_COUNT
+pub fn create(db_path: &str) -> bool
+{
+    create_keeping(db_path, None)
+}
+
+/// backs up the database before a save, unless the newest backup is less than BACKUP_INTERVAL_MINUTES old
+pub fn rotate(db_path: &str) -> bool
+{
+    let recent = list(db_path).first().is_some_and(|newest| Local::now().naive_local() - newest.date < Duration::minutes(BACKUP_INTERVAL_MINUTES));
+
+    if recent
+    {
+        return true;
+    }
+
+    create(db_path)
+}
+
+// like create, but never prunes the backup at keep, eg. the one being restored
+fn create_keeping(db_path: &str, keep: Option<&Path>) -> bool
+{
+    // a broken database isn't worth keeping, and would push a good backup out
+    let current = util::read_file(db_path);
+
+    if serde_json::from_str::<Database>(&current).is_err()
+    {
+        return false;
+    }
+
+    if fs::create_dir_all(dir(db_path)).is_err()
+    {
+        return false;
+    }
+
+    let path = dir(db_path).join(format!("{}-{}.json", stem(db_path), Local::now().format(STAMP_FORMAT)));
+
+    if !util::write_file(&path.to_string_lossy(), &current)
+    {
+        return false;
+    }
+
+    for old in list(db_path).iter().filter(|backup| Some(backup.path.as_path()) != keep).skip(BACKUP_COUNT)
+    {
+        let _ = fs::remove_file(&old.path);
+    }
+
+    true
+}
+
+/// newest first
+pub fn list(db_path: &str) -> Vec<Backup>
+{
+    let prefix = format!("{}-", stem(db_path));
+
+    let entries = match fs::read_dir(dir(db_path))
+    {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new()
+    };
+
+    let mut backups: Vec<Backup> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let stamp = name.strip_prefix(&prefix)?.strip_suffix(".json")?.to_string();
+            let date = NaiveDateTime::parse_from_str(&stamp, STAMP_FORMAT).ok()?;
+
+            Some(Backup { path: entry.path(), date })
+        })
+        .collect();
+
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.date));
+
+    backups
+}
+
+/// the database a backup holds, None when it can't be read
+pub fn load(backup: &Backup) -> Option<Database>
+{
+    serde_json::from_str(&util::read_file(&backup.path.to_string_lossy())).ok()
+}
+
+/// puts a backup back in place of the database, the database being replaced gets backed up first
+///
+/// returns the restored database, None when the backup is unreadable or couldn't be written
+pub fn restore(db_path: &str, backup: &Backup) -> Option<Database>
+{
+    let contents = util::read_file(&backup.path.to_string_lossy());
+    let restored: Database = serde_json::from_str(&contents).ok()?;
+
+    create_keeping(db_path, Some(&backup.path));
+
+    if !util::write_file(db_path, &contents)
+    {
+        return None;
+    }
+
+    Some(restored)
+}
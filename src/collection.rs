@@ -0,0 +1,123 @@
+//! named collections (binders) that share one database
+
+use crate::util::{Database, DEFAULT_COLLECTION};
+
+/// the active collection's name, or "all collections" when none is picked
+pub fn active_name(db: &Database) -> String
+{
+    match &db.active
+    {
+        None => "all collections".to_string(),
+        Some(name) => name.clone()
+    }
+}
+
+/// the collection new cards go into when nothing specific is picked
+pub fn target(db: &Database) -> String
+{
+    match &db.active
+    {
+        None => DEFAULT_COLLECTION.to_string(),
+        Some(name) => name.clone()
+    }
+}
+
+/// whether cards in this collection show while db's active collection is picked
+pub fn in_view(db: &Database, collection: &str) -> bool
+{
+    match &db.active
+    {
+        None => true,
+        Some(name) => name == collection
+    }
+}
+
+/// indices into db.cards for the cards the active collection can see, in menu order
+pub fn view_indices(db: &Database) -> Vec<usize>
+{
+    db.cards.iter()
+        .enumerate()
+        .filter(|(_, card)| in_view(db, &card.collection))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// a copy of the database holding only the active collection's cards, for valuation and reports
+pub fn view(db: &Database) -> Database
+{
+    let mut view = db.clone();
+    view.cards.retain(|card| in_view(db, &card.collection));
+
+    view
+}
+
+/// adds an empty collection, false when the name is blank or taken
+pub fn create(db: &mut Database, name: &str) -> bool
+{
+    let name = name.trim();
+
+    if name.is_empty() || db.collections.iter().any(|collection| collection == name)
+    {
+        return false;
+    }
+
+    db.collections.push(name.to_string());
+
+    true
+}
+
+/// picks the collection the menu and reports work on, None for all of them, false when there's no such collection
+pub fn switch(db: &mut Database, name: Option<&str>) -> bool
+{
+    match name
+    {
+        None => db.active = None,
+        Some(name) => {
+            if !db.collections.iter().any(|collection| collection == name)
+            {
+                return false;
+            }
+
+            db.active = Some(name.to_string());
+        }
+    }
+
+    true
+}
+
+/// moves one card (a position in db.cards) into another collection, false when either doesn't exist
+pub fn move_card(db: &mut Database, index: usize, to: &str) -> bool
+{
+    if index >= db.cards.len() || !db.collections.iter().any(|collection| collection == to)
+    {
+        return false;
+    }
+
+    db.cards[index].collection = to.to_string();
+
+    true
+}
+
+/// only empty collections can go, so deleting never loses cards
+pub fn delete(db: &mut Database, name: &str) -> bool
+{
+    if db.cards.iter().any(|card| card.collection == name)
+    {
+        return false;
+    }
+
+    let count = db.collections.len();
+    db.collections.retain(|collection| collection != name);
+
+    if db.collections.is_empty()
+    {
+        db.collections.push(DEFAULT_COLLECTION.to_string());
+    }
+
+    if db.active.as_deref() == Some(name)
+    {
+        db.active = None;
+    }
+
+    db.collections.len() != count
+}
@@ -0,0 +1,223 @@
+//! tcgplayer prices everything in US dollars, this shows them in the user's own currency instead
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Local};
+
+use crate::settings::{self, Settings};
+use crate::util;
+
+/// what every price is kept in, since it's what tcgplayer answers in
+pub const BASE_CURRENCY: &str = "USD";
+/// points the exchange rate table somewhere else, eg. a dead address in tests
+pub const RATES_URL_VAR: &str = "ONEPIECECARDS_RATES_URL";
+
+// the rate table answers in the same layout, eg. {"base":"USD","date":"2026-10-16","rates":{"EUR":0.92}}
+const RATES_URL: &str = "https://api.frankfurter.app/latest?from=USD";
+const RATES_FILE: &str = "rates.json";
+// after a failed fetch the saved rates are used this long before trying again, so offline every screen isn't a timeout
+const RETRY_MINUTES: i64 = 60;
+
+/// the exchange rate table, saved as rates.json in the config folder
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Rates
+{
+    /// the currency the rates convert from, always USD
+    pub base: String,
+    /// the day the rates are from, not the day they were fetched
+    pub date: String,
+    /// units of each currency per dollar, by currency code
+    pub rates: BTreeMap<String, f64>,
+    /// when fetching new rates last failed, None since the last good fetch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed: Option<DateTime<Local>>
+}
+
+/// the currency prices are shown in, with what it takes to get there from dollars
+#[derive(serde::Serialize, Clone)]
+pub struct Currency
+{
+    /// eg. "EUR"
+    pub code: String,
+    /// eg. "€"
+    pub symbol: String,
+    /// how much one US dollar is in this currency
+    pub rate: f64,
+    /// None when no conversion is happening
+    pub date: Option<String>
+}
+
+/// plain dollars, no conversion
+pub fn usd() -> Currency
+{
+    Currency { code: BASE_CURRENCY.to_string(), symbol: "$".to_string(), rate: 1.0, date: None }
+}
+
+fn symbol(code: &str) -> String
+{
+    match code
+    {
+        "USD" => "$".to_string(),
+        "EUR" => "€".to_string(),
+        "GBP" => "£".to_string(),
+        "JPY" => "¥".to_string(),
+        "CAD" => "CA$".to_string(),
+        "AUD" => "A$".to_string(),
+        "CHF" => "CHF ".to_string(),
+        _ => format!("{} ", code)
+    }
+}
+
+fn rates_path() -> String
+{
+    settings::config_dir().join(RATES_FILE).to_string_lossy().to_string()
+}
+
+fn fetch_rates() -> Option<Rates>
+{
+    let client = reqwest::blocking::Client::builder().timeout(std::time::Duration::from_secs(10)).build().ok()?;
+
+    let url = std::env::var(RATES_URL_VAR).unwrap_or(RATES_URL.to_string());
+
+    let body = client.get(url).send().ok()?.error_for_status().ok()?.text().ok()?;
+    let rates: Rates = serde_json::from_str(&body).ok()?;
+
+    if std::fs::create_dir_all(settings::config_dir()).is_ok()
+    {
+        util::write_file(&rates_path(), &body);
+    }
+
+    Some(rates)
+}
+
+// keeps the old table, or an empty one, with the time of the failure so the next fetch waits
+fn record_failure(saved: Option<Rates>)
+{
+    let mut failed = saved.unwrap_or(Rates { base: BASE_CURRENCY.to_string(), date: String::new(), rates: BTreeMap::new(), failed: None });
+    failed.failed = Some(Local::now());
+
+    if std::fs::create_dir_all(settings::config_dir()).is_ok()
+    {
+        util::write_file(&rates_path(), &serde_json::to_string(&failed).unwrap());
+    }
+}
+
+/// the saved table while it's less than a day old, otherwise a fresh one, falling back to the old one when offline
+///
+/// a failed fetch isn't tried again for RETRY_MINUTES
+pub fn rates(settings: &Settings) -> Option<Rates>
+{
+    let saved: Option<Rates> = serde_json::from_str(&util::read_file(&rates_path())).ok();
+
+    // the table written after a failure has no rates when there were none to keep
+    let usable = |saved: Option<Rates>| saved.filter(|rates| !rates.rates.is_empty());
+
+    let fresh = match saved.as_ref().map(|rates| rates.failed)
+    {
+        None => false,
+        Some(Some(failed)) => Local::now() - failed < Duration::minutes(RETRY_MINUTES),
+        Some(None) => !util::cache_too_old(util::cache_old(&rates_path()))
+    };
+
+    if !settings.fetch_rates || fresh
+    {
+        return usable(saved);
+    }
+
+    match fetch_rates()
+    {
+        Some(rates) => Some(rates),
+        None => {
+            record_failure(saved.clone());
+            usable(saved)
+        }
+    }
+}
+
+/// the chosen currency, falling back to dollars when there's no rate for it
+pub fn load(settings: &Settings) -> Currency
+{
+    try_load(settings).unwrap_or_else(|_| usd())
+}
+
+/// like load, with why there's no rate for the chosen currency
+pub fn try_load(settings: &Settings) -> Result<Currency, String>
+{
+    if settings.currency == BASE_CURRENCY
+    {
+        return Ok(usd());
+    }
+
+    let found = rates(settings).and_then(|rates| {
+        let rate = *rates.rates.get(&settings.currency)?;
+        (rates.base == BASE_CURRENCY).then_some((rate, rates.date))
+    });
+
+    match found
+    {
+        Some((rate, date)) => Ok(Currency { code: settings.currency.clone(), symbol: symbol(&settings.currency), rate, date: Some(date) }),
+        None => Err(format!("no exchange rate for {}, showing prices in {}", settings.currency, BASE_CURRENCY))
+    }
+}
+
+/// a dollar amount in the currency, without rounding
+pub fn convert(currency: &Currency, usd_amount: f64) -> f64
+{
+    usd_amount * currency.rate
+}
+
+/// eg. €11.50
+pub fn format(currency: &Currency, usd_amount: f64) -> String
+{
+    let amount = convert(currency, usd_amount);
+
+    // an empty sum is -0.0, and nothing that rounds to zero should show a sign
+    let amount = if (amount * 100.0).round() == 0.0 { 0.0 } else { amount };
+
+    if amount < 0.0
+    {
+        format!("-{}{:.2}", currency.symbol, -amount)
+    }
+    else
+    {
+        format!("{}{:.2}", currency.symbol, amount)
+    }
+}
+
+/// eg. +€1.50 or -€0.25
+pub fn signed(currency: &Currency, usd_amount: f64) -> String
+{
+    let formatted = format(currency, usd_amount);
+
+    if formatted.starts_with('-')
+    {
+        formatted
+    }
+    else
+    {
+        format!("+{}", formatted)
+    }
+}
+
+/// eg. "prices in EUR at 0.9200 per USD, rates from 2026-10-16", empty when showing dollars
+pub fn rate_note(currency: &Currency) -> String
+{
+    match &currency.date
+    {
+        None => String::new(),
+        Some(date) => format!("prices in {} at {:.4} per {}, rates from {}", currency.code, currency.rate, BASE_CURRENCY, date)
+    }
+}
+
+/// every currency the rate table knows about, plus dollars
+pub fn codes(settings: &Settings) -> Vec<String>
+{
+    let mut codes = vec![BASE_CURRENCY.to_string()];
+
+    if let Some(rates) = rates(settings)
+    {
+        codes.extend(rates.rates.into_keys().filter(|code| code != BASE_CURRENCY));
+    }
+
+    codes
+}
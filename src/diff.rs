@@ -0,0 +1,210 @@
+//! what changed between two snapshots, and how much of the value change came from prices vs. cards
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde_json::Value;
+
+use crate::currency::{self, Currency};
+use crate::settings::Settings;
+use crate::snapshot::Snapshot;
+use crate::util::{self, format_id};
+use crate::valuation;
+
+/// one product's quantity and price at both ends of a diff
+#[derive(serde::Serialize, Clone)]
+pub struct CardChange
+{
+    /// formatted, eg. "1001"
+    pub product_id: String,
+    /// with the op code and pre release tag, what the menu shows
+    pub name: String,
+    /// copies in the old snapshot
+    pub old_quantity: u32,
+    /// copies in the new snapshot
+    pub new_quantity: u32,
+    /// one near mint copy, the new price when the old snapshot had none
+    pub old_price: f64,
+    /// one near mint copy
+    pub new_price: f64,
+    /// the copies held before, after their condition multipliers
+    pub old_value: f64,
+    /// the copies held after, after their condition multipliers
+    pub new_value: f64,
+    /// what the copies held before gained or lost from the price moving
+    pub price_effect: f64,
+    /// what adding or removing copies did, at the new price
+    pub collection_effect: f64
+}
+
+/// everything that changed between two snapshots, the value change split into prices and cards
+#[derive(serde::Serialize, Clone)]
+pub struct Diff
+{
+    /// what the old side is called, eg. "snapshot 2026-10-01 10:00:00"
+    pub old_label: String,
+    /// what the new side is called, eg. "now"
+    pub new_label: String,
+    /// the whole collection before
+    pub old_value: f64,
+    /// the whole collection after
+    pub new_value: f64,
+    /// how much of the change came from prices moving
+    pub price_effect: f64,
+    /// how much of the change came from cards coming and going
+    pub collection_effect: f64,
+    /// products that weren't held before, biggest change first
+    pub added: Vec<CardChange>,
+    /// products no longer held, biggest change first
+    pub removed: Vec<CardChange>,
+    /// products held at both ends in different numbers, biggest change first
+    pub quantity_changed: Vec<CardChange>,
+    /// products held before whose price moved, biggest price effect first
+    pub repriced: Vec<CardChange>
+}
+
+// copies per product, and the same copies weighted by their condition multipliers
+fn quantities(snapshot: &Snapshot, settings: &Settings) -> (BTreeMap<String, u32>, BTreeMap<String, f64>)
+{
+    let mut quantities: BTreeMap<String, u32> = BTreeMap::new();
+    let mut weights: BTreeMap<String, f64> = BTreeMap::new();
+
+    for card in &snapshot.db.cards
+    {
+        let product_id = format_id(card.product_id.clone());
+
+        *quantities.entry(product_id.clone()).or_insert(0) += 1;
+        *weights.entry(product_id).or_insert(0.0) += valuation::multiplier(card.condition, settings);
+    }
+
+    (quantities, weights)
+}
+
+/// prices missing from both snapshots (backups never stored any) come from today's prices,
+/// and a snapshot taken at another price basis counts as having none
+pub fn compare(old: &Snapshot, new: &Snapshot, old_label: &str, new_label: &str, mut webcache: HashMap<String, String>, settings: &Settings) -> (Diff, HashMap<String, String>)
+{
+    let (old_quantities, old_weights) = quantities(old, settings);
+    let (new_quantities, new_weights) = quantities(new, settings);
+
+    let old_prices = if old.basis == settings.price_basis { old.prices.clone() } else { BTreeMap::new() };
+    let new_prices = if new.basis == settings.price_basis { new.prices.clone() } else { BTreeMap::new() };
+
+    let product_ids: BTreeSet<String> = old_quantities.keys().chain(new_quantities.keys()).cloned().collect();
+
+    let mut changes: Vec<CardChange> = Vec::new();
+
+    for product_id in product_ids
+    {
+        let new_price = match new_prices.get(&product_id).or(old_prices.get(&product_id))
+        {
+            Some(price) => *price,
+            None => {
+                let price_request = valuation::price(&product_id, settings.price_basis, webcache);
+                webcache = price_request.1;
+
+                price_request.0.unwrap_or(0.0)
+            }
+        };
+
+        // without an old price there's nothing to say the price moved
+        let old_price = *old_prices.get(&product_id).unwrap_or(&new_price);
+
+        let old_quantity = *old_quantities.get(&product_id).unwrap_or(&0);
+        let new_quantity = *new_quantities.get(&product_id).unwrap_or(&0);
+
+        let old_weight = *old_weights.get(&product_id).unwrap_or(&0.0);
+        let new_weight = *new_weights.get(&product_id).unwrap_or(&0.0);
+
+        let product_request = util::get_product_details(&product_id, webcache.clone());
+        webcache = product_request.1;
+
+        let product_info: Value = serde_json::from_str(&product_request.0).unwrap_or_default();
+
+        changes.push(CardChange {
+            product_id,
+            name: util::display_name(&product_info),
+            old_quantity,
+            new_quantity,
+            old_price,
+            new_price,
+            old_value: old_weight * old_price,
+            new_value: new_weight * new_price,
+            price_effect: old_weight * (new_price - old_price),
+            collection_effect: (new_weight - old_weight) * new_price
+        });
+    }
+
+    let by_effect = |a: &CardChange, b: &CardChange| (b.price_effect + b.collection_effect).abs().total_cmp(&(a.price_effect + a.collection_effect).abs()).then(a.name.cmp(&b.name));
+
+    let mut added: Vec<CardChange> = changes.iter().filter(|change| change.old_quantity == 0 && change.new_quantity > 0).cloned().collect();
+    let mut removed: Vec<CardChange> = changes.iter().filter(|change| change.old_quantity > 0 && change.new_quantity == 0).cloned().collect();
+    let mut quantity_changed: Vec<CardChange> = changes.iter().filter(|change| change.old_quantity > 0 && change.new_quantity > 0 && change.old_quantity != change.new_quantity).cloned().collect();
+    let mut repriced: Vec<CardChange> = changes.iter().filter(|change| change.old_quantity > 0 && change.old_price != change.new_price).cloned().collect();
+
+    added.sort_by(by_effect);
+    removed.sort_by(by_effect);
+    quantity_changed.sort_by(by_effect);
+    repriced.sort_by(|a, b| b.price_effect.abs().total_cmp(&a.price_effect.abs()).then(a.name.cmp(&b.name)));
+
+    let diff = Diff {
+        old_label: old_label.to_string(),
+        new_label: new_label.to_string(),
+        old_value: changes.iter().map(|change| change.old_value).sum(),
+        new_value: changes.iter().map(|change| change.new_value).sum(),
+        price_effect: changes.iter().map(|change| change.price_effect).sum(),
+        collection_effect: changes.iter().map(|change| change.collection_effect).sum(),
+        added,
+        removed,
+        quantity_changed,
+        repriced
+    };
+
+    (diff, webcache)
+}
+
+/// the diff as plain text for the console or diff.txt, prices in the chosen currency
+pub fn text(diff: &Diff, currency: &Currency) -> String
+{
+    let mut output = String::new();
+
+    output.push_str(&format!("Changes from {} to {}\n\n", diff.old_label, diff.new_label));
+    let money = |value: f64| currency::format(currency, value);
+    let signed_money = |value: f64| currency::signed(currency, value);
+
+    if currency.date.is_some()
+    {
+        output.push_str(&format!("{}\n\n", currency::rate_note(currency)));
+    }
+
+    output.push_str(&format!("Value: {} -> {} ({})\n", money(diff.old_value), money(diff.new_value), signed_money(diff.new_value - diff.old_value)));
+    output.push_str(&format!("  from price changes:      {}\n", signed_money(diff.price_effect)));
+    output.push_str(&format!("  from collection changes: {}\n", signed_money(diff.collection_effect)));
+
+    let sections: [(&str, &Vec<CardChange>); 4] = [("Added", &diff.added), ("Removed", &diff.removed), ("Quantity Changes", &diff.quantity_changed), ("Price Changes", &diff.repriced)];
+
+    for (title, changes) in sections
+    {
+        output.push_str(&format!("\n{}:\n", title));
+
+        if changes.is_empty()
+        {
+            output.push_str("  nothing\n");
+        }
+
+        for change in changes
+        {
+            let line = match title
+            {
+                "Added" => format!("  +{} {} ({} each, {})", change.new_quantity, change.name, money(change.new_price), signed_money(change.collection_effect)),
+                "Removed" => format!("  -{} {} ({} each, {})", change.old_quantity, change.name, money(change.new_price), signed_money(change.collection_effect)),
+                "Quantity Changes" => format!("  {}: {} -> {} ({})", change.name, change.old_quantity, change.new_quantity, signed_money(change.collection_effect)),
+                _ => format!("  {}: {} -> {} (x{} held, {})", change.name, money(change.old_price), money(change.new_price), change.old_quantity, signed_money(change.price_effect))
+            };
+
+            output.push_str(&line);
+            output.push('\n');
+        }
+    }
+
+    output
+}
@@ -0,0 +1,183 @@
+//! exporters for getting the collection into spreadsheets and tcgplayer
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::Value;
+
+use crate::currency::{self, Currency};
+use crate::settings::Settings;
+use crate::util::{self, format_id, Database};
+use crate::valuation::{self, Condition};
+
+/// one product in one condition in an export, with every copy of it counted together
+pub struct ExportRow
+{
+    /// formatted, eg. "1001"
+    pub product_id: String,
+    /// copies held
+    pub quantity: u32,
+    /// tcgplayer's product name, without the op code
+    pub name: String,
+    /// the op code, eg. "OP01-016"
+    pub number: String,
+    /// eg. "Romance Dawn"
+    pub set_name: String,
+    /// eg. "OP01", what the mass entry box wants
+    pub set_code: String,
+    /// eg. "Super Rare"
+    pub rarity: String,
+    /// eg. "One Piece Card Game"
+    pub product_line: String,
+    /// what shape these copies are in
+    pub condition: Condition,
+    /// one copy in dollars, None when tcgplayer has no price of that kind
+    pub market_price: Option<f64>,
+    /// the cheapest listing in dollars
+    pub low_price: Option<f64>,
+    /// the median listing in dollars
+    pub median_price: Option<f64>,
+    /// every copy at the settings' price basis after the condition multiplier, the way the reports value them
+    pub total_value: Option<f64>,
+    /// the card's tcgplayer page
+    pub url: String
+}
+
+/// one row per product and condition, duplicate cards are summed into the quantity
+pub fn rows(db: &Database, mut webcache: HashMap<String, String>, settings: &Settings) -> (Vec<ExportRow>, HashMap<String, String>)
+{
+    let mut quantities: BTreeMap<(String, Condition), u32> = BTreeMap::new();
+
+    for card in &db.cards
+    {
+        *quantities.entry((format_id(card.product_id.clone()), card.condition)).or_insert(0) += 1;
+    }
+
+    let mut rows: Vec<ExportRow> = Vec::new();
+
+    for ((product_id, condition), quantity) in quantities
+    {
+        let product_request = util::get_product_details(&product_id, webcache.clone());
+        webcache = product_request.1;
+
+        let product_info: Value = serde_json::from_str(&product_request.0).unwrap_or_default();
+        let number = product_info["customAttributes"]["number"].as_str().unwrap_or_default().to_string();
+
+        // the set code isn't always filled in, but the op code always starts with it
+        let set_code = match product_info["setCode"].as_str()
+        {
+            Some(code) if !code.is_empty() => code.to_string(),
+            _ => number.split('-').next().unwrap_or_default().to_string()
+        };
+
+        rows.push(ExportRow {
+            url: format!("https://www.tcgplayer.com/product/{}", product_id),
+            product_id,
+            quantity,
+            name: product_info["productName"].as_str().unwrap_or_default().to_string(),
+            number,
+            set_name: util::set_name(&product_info),
+            set_code,
+            rarity: product_info["rarityName"].as_str().unwrap_or_default().to_string(),
+            product_line: product_info["productLineName"].as_str().unwrap_or_default().to_string(),
+            condition,
+            market_price: product_info["marketPrice"].as_f64(),
+            low_price: product_info["lowestPrice"].as_f64(),
+            median_price: product_info["medianPrice"].as_f64(),
+            total_value: valuation::unit_price(&product_info, settings.price_basis).map(|price| price * valuation::multiplier(condition, settings) * quantity as f64)
+        });
+    }
+
+    // sorted on every field that could tie so the files diff cleanly
+    rows.sort_by(|a, b| {
+        a.set_name.cmp(&b.set_name)
+            .then(a.number.cmp(&b.number))
+            .then(a.name.cmp(&b.name))
+            .then(a.product_id.cmp(&b.product_id))
+            .then(a.condition.cmp(&b.condition))
+    });
+
+    (rows, webcache)
+}
+
+/// prices are converted to the display currency, with the currency and rate date in every row so sheets can be merged
+pub fn csv(db: &Database, webcache: HashMap<String, String>, settings: &Settings, currency: &Currency) -> (String, HashMap<String, String>)
+{
+    let rows_request = rows(db, webcache, settings);
+
+    let mut output = String::new();
+    output.push_str("Quantity,Product ID,Name,Number,Set,Set Code,Rarity,Condition,Product Line,Market Price,Low Price,Median Price,Total Value,Currency,Rate Date,URL\n");
+
+    let format_price = |price: Option<f64>| format_price(price.map(|price| currency::convert(currency, price)));
+
+    for row in rows_request.0
+    {
+        let fields = [
+            row.quantity.to_string(),
+            row.product_id,
+            row.name,
+            row.number,
+            row.set_name,
+            row.set_code,
+            row.rarity,
+            valuation::condition_name(row.condition).to_string(),
+            row.product_line,
+            format_price(row.market_price),
+            format_price(row.low_price),
+            format_price(row.median_price),
+            format_price(row.total_value),
+            currency.code.clone(),
+            currency.date.clone().unwrap_or_default(),
+            row.url
+        ];
+
+        output.push_str(&fields.iter().map(|field| csv_field(field)).collect::<Vec<String>>().join(","));
+        output.push('\n');
+    }
+
+    (output, rows_request.1)
+}
+
+/// the layout tcgplayer's mass entry box takes, eg. `2 Nami [OP01]`
+///
+/// the box has no condition, so every condition of a product goes on one line
+pub fn tcgplayer(db: &Database, webcache: HashMap<String, String>, settings: &Settings) -> (String, HashMap<String, String>)
+{
+    let rows_request = rows(db, webcache, settings);
+
+    let mut lines: Vec<(String, u32, String)> = Vec::new();
+
+    for row in rows_request.0
+    {
+        // the rows are sorted by product before condition, so copies of one product sit together
+        match lines.last_mut()
+        {
+            Some(line) if line.0 == row.product_id => line.1 += row.quantity,
+            _ => lines.push((row.product_id, row.quantity, format!("{} [{}]", row.name, row.set_code)))
+        }
+    }
+
+    let output: String = lines.iter().map(|(_, quantity, card)| format!("{} {}\n", quantity, card)).collect();
+
+    (output, rows_request.1)
+}
+
+fn format_price(price: Option<f64>) -> String
+{
+    match price
+    {
+        None => String::new(),
+        Some(price) => format!("{:.2}", price)
+    }
+}
+
+fn csv_field(field: &str) -> String
+{
+    if field.contains(',') || field.contains('"') || field.contains('\n')
+    {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    }
+    else
+    {
+        field.to_string()
+    }
+}
@@ -0,0 +1,143 @@
+//! what a card does in the game, read out of the `customAttributes` in tcgplayer's product details
+//!
+//! tcgplayer keeps most of these as text, eg. "cost": "3" or "counterplus": "+1000", and "-" for none
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::report;
+use crate::util;
+
+/// a card's game data, empty where tcgplayer doesn't say
+#[derive(serde::Serialize, Clone, Default)]
+pub struct CardData
+{
+    /// formatted, eg. "1001"
+    pub product_id: String,
+    /// with the op code and pre release tag, what the menu shows
+    pub name: String,
+    /// eg. "OP01-024"
+    pub number: String,
+    /// eg. "Romance Dawn"
+    pub set_name: String,
+    /// eg. "Super Rare"
+    pub rarity: String,
+    /// eg. "Leader", "Character", "Event" or "Stage"
+    pub card_type: String,
+    /// eg. ["Red"], leaders can have two
+    pub colors: Vec<String>,
+    /// the battle attribute, eg. "Strike" or "Slash"
+    pub attributes: Vec<String>,
+    /// eg. "Straw Hat Crew"
+    pub subtypes: Vec<String>,
+    /// None for leaders and for cards without one
+    pub cost: Option<u32>,
+    /// None for events and cards without one
+    pub power: Option<u32>,
+    /// what the card adds when used as a counter, None when it can't be
+    pub counter: Option<u32>,
+    /// only leaders have life
+    pub life: Option<u32>,
+    /// the effect text without html
+    pub effect: String
+}
+
+// "5000", "+1000" and "1,000" are numbers, "-" and "" aren't
+fn number(value: &Value) -> Option<u32>
+{
+    if let Some(number) = value.as_u64()
+    {
+        return u32::try_from(number).ok();
+    }
+
+    let digits: String = value.as_str()?.chars().filter(|character| character.is_ascii_digit()).collect();
+
+    digits.parse().ok()
+}
+
+// a list or a single string, with several subtypes in one string split on ; or /
+fn list(value: &Value) -> Vec<String>
+{
+    let values: Vec<&str> = match value
+    {
+        Value::Array(values) => values.iter().filter_map(|value| value.as_str()).collect(),
+        Value::String(text) => vec![text.as_str()],
+        _ => Vec::new()
+    };
+
+    values.iter()
+        .flat_map(|text| text.split([';', '/']))
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty() && text != "-")
+        .collect()
+}
+
+/// the game data in one product's details json
+pub fn parse(product_info: &Value) -> CardData
+{
+    let attributes = &product_info["customAttributes"];
+
+    let subtypes = if attributes["subtypes"].is_null() { &attributes["subtype"] } else { &attributes["subtypes"] };
+    let counter = if attributes["counterplus"].is_null() { &attributes["counter"] } else { &attributes["counterplus"] };
+
+    CardData {
+        product_id: product_info["productId"].as_number().map(|product_id| util::format_id(product_id.clone())).unwrap_or_default(),
+        name: util::display_name(product_info),
+        number: attributes["number"].as_str().unwrap_or_default().to_string(),
+        set_name: util::set_name(product_info),
+        rarity: product_info["rarityName"].as_str().unwrap_or_default().to_string(),
+        card_type: list(&attributes["cardType"]).join(" "),
+        colors: list(&attributes["color"]),
+        attributes: list(&attributes["attribute"]),
+        subtypes: list(subtypes),
+        cost: number(&attributes["cost"]),
+        power: number(&attributes["power"]),
+        counter: number(counter).filter(|counter| *counter > 0),
+        life: number(&attributes["life"]),
+        effect: report::plain_text(attributes["description"].as_str().unwrap_or_default())
+    }
+}
+
+/// the game data for one product, from the cache when it's there
+pub fn lookup(formatted_product_id: &str, webcache: HashMap<String, String>) -> (CardData, HashMap<String, String>)
+{
+    let product_request = util::get_product_details(formatted_product_id, webcache);
+    let product_info: Value = serde_json::from_str(&product_request.0).unwrap_or_default();
+
+    (parse(&product_info), product_request.1)
+}
+
+/// eg. "Red Character, cost 3, 5000 power, +1000 counter, Straw Hat Crew"
+pub fn summary(card: &CardData) -> String
+{
+    let mut parts: Vec<String> = vec![format!("{} {}", card.colors.join("/"), card.card_type).trim().to_string()];
+
+    if let Some(cost) = card.cost
+    {
+        parts.push(format!("cost {}", cost));
+    }
+
+    if let Some(life) = card.life
+    {
+        parts.push(format!("{} life", life));
+    }
+
+    if let Some(power) = card.power
+    {
+        parts.push(format!("{} power", power));
+    }
+
+    if let Some(counter) = card.counter
+    {
+        parts.push(format!("+{} counter", counter));
+    }
+
+    if !card.subtypes.is_empty()
+    {
+        parts.push(card.subtypes.join("/"));
+    }
+
+    parts.retain(|part| !part.is_empty());
+    parts.join(", ")
+}
@@ -1,274 +1,364 @@
-use std::collections::HashMap;
+//! the html report, printable binder sheets, diff page and dashboard, filled in from minijinja templates
+
+use std::{collections::HashMap, path::Path};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use minijinja::{context, Environment, Value as TemplateValue};
+
+use crate::currency::{self, Currency};
+use crate::diff::Diff;
+use crate::images;
+use crate::report::{self, ReportCard};
+use crate::settings::{self, ReportMode, Settings};
+use crate::util::{self, Database};
+
+const DEFAULT_TEMPLATE: &str = include_str!("templates/report.html");
+const DARK_THEME: &str = include_str!("templates/dark.css");
+const LIGHT_THEME: &str = include_str!("templates/light.css");
+const BINDER_TEMPLATE: &str = include_str!("templates/binder.html");
+const DIFF_TEMPLATE: &str = include_str!("templates/diff.html");
+const DASHBOARD_TEMPLATE: &str = include_str!("templates/dashboard.html");
+/// where generate_diff writes the diff page
+pub const DIFF_LOC: &str = "diff.html";
+
+const BINDER_PAGE_SIZE: usize = 9;
+
+// folder mode puts the page at report/report.html by default, with the images next to it in report/images
+const REPORT_IMAGES: &str = "images";
+// cdn mode links the 400 pixel wide images, the full size ones are several times bigger than a report needs
+const CDN_IMAGE_SIZE: &str = "400w";
+
+#[derive(serde::Serialize)]
+struct HtmlCard
+{
+    #[serde(flatten)]
+    card: ReportCard,
+    image: String
+}
+
+#[derive(serde::Serialize, Clone)]
+struct BinderCard
+{
+    product_id: String,
+    name: String,
+    number: String,
+    set_name: String,
+    quantity: u32,
+    /// None when the copies in the pocket are in different conditions, and so worth different amounts
+    market_value: Option<f64>,
+    total_value: f64,
+    image: String
+}
+
+#[derive(serde::Serialize)]
+struct HtmlSet
+{
+    name: String,
+    count: u32,
+    subtotal: f64,
+    cards: Vec<HtmlCard>
+}
+
+/// writes the html report to path (usually report_path), with images laid out the way the settings ask for
+pub fn generate(db: Database, webcache: HashMap<String, String>, settings: &Settings, path: &str) -> (Result<(), String>, HashMap<String, String>)
+{
+    let mut thumbnails: HashMap<String, Vec<u8>> = HashMap::new();
+
+    let page_request = page(&db, webcache, settings, &mut thumbnails);
+    let webcache = page_request.1;
+
+    let html_code = match page_request.0
+    {
+        Err(error) => return (Err(error), webcache),
+        Ok(html_code) => html_code
+    };
+
+    if settings.report_mode == ReportMode::Folder && !write_images(path, &thumbnails)
+    {
+        return (Err("could not write the report images!".to_string()), webcache);
+    }
+
+    (write(path, &html_code), webcache)
+}
+
+/// the report page itself, thumbnails made along the way are kept for folder mode and the dashboard server
+pub fn page(db: &Database, mut webcache: HashMap<String, String>, settings: &Settings, thumbnails: &mut HashMap<String, Vec<u8>>) -> (Result<String, String>, HashMap<String, String>)
+{
+    let data_request = report::gather(db, webcache.clone(), settings);
+    webcache = data_request.1;
+
+    let data = data_request.0;
+
+    let mut sets: Vec<HtmlSet> = Vec::new();
 
-use serde_json::{Number, Value};
+    for set in &data.sets
+    {
+        let mut cards: Vec<HtmlCard> = Vec::new();
+
+        for card in &set.cards
+        {
+            let image_request = image_src(&card.product_id, settings, thumbnails, webcache.clone());
+            webcache = image_request.1;
+
+            cards.push(HtmlCard { card: card.clone(), image: image_request.0 });
+        }
 
-use crate::util::{self, format_id, Database};
+        sets.push(HtmlSet { name: set.name.clone(), count: set.count, subtotal: set.subtotal, cards });
+    }
+
+    let rendered = render("report.html", DEFAULT_TEMPLATE, context! {
+        title => "One Piece TCG Card List",
+        theme_css => TemplateValue::from_safe_string(theme_css(settings)),
+        total_worth => data.total_worth,
+        card_count => data.card_count,
+        price_basis => data.price_basis,
+        unknown_prices => data.unknown_prices,
+        rate_note => currency::rate_note(&data.currency),
+        sets => sets,
+        rarities => data.rarities,
+        colors => data.colors,
+        card_types => data.card_types,
+        top => data.top
+    }, &data.currency);
+
+    (rendered.map_err(|error| format!("could not fill in the report template! {}", error)), webcache)
+}
 
-pub fn generate(db: Database, mut webcache: HashMap<String, String>) -> (bool, HashMap<String, String>)
+/// print-ready binder inventory sheets written to path (usually binder_path), a 3x3 grid of cards per page with the grand total at the end
+pub fn generate_binder(db: Database, mut webcache: HashMap<String, String>, settings: &Settings, path: &str) -> (Result<(), String>, HashMap<String, String>)
 {
-    let total_worth = util::total_worth(&db, webcache.clone());
-
-    webcache = total_worth.1;
-
-    let html_code_start = format!(r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>One Piece TCG Card List</title>
-    <style>
-        body {{
-            font-family: Arial, sans-serif;
-            background-color: #0a192f;
-            color: #e6f1ff;
-            margin: 0;
-            padding: 0;
-        }}
-
-        .header {{
-            background-color: #112240;
-            padding: 10px 20px;
-            text-align: center;
-            position: sticky;
-            top: 0;
-            z-index: 1000;
-        }}
-
-        .header h2 {{
-            color: #64ffda;
-            margin: 0;
-        }}
-
-        h1 {{
-            text-align: center;
-            color: #64ffda;
-            padding: 20px 0;
-        }}
-
-        .card-container {{
-            display: flex;
-            flex-wrap: wrap;
-            justify-content: center;
-            gap: 20px;
-            padding: 20px;
-        }}
-
-        .card {{
-            background-color: #112240;
-            border-radius: 10px;
-            box-shadow: 0 4px 6px rgba(0, 0, 0, 0.1);
-            display: flex;
-            width: 100%;
-            max-width: 600px;
-            overflow: hidden;
-            flex-direction: row;
-        }}
-
-        .card-image {{
-            width: 200px;
-            height: 280px;
-            object-fit: cover;
-        }}
-
-        .card-info {{
-            padding: 20px;
-            flex-grow: 1;
-            display: flex;
-            flex-direction: column;
-        }}
-
-        .card-name {{
-            font-size: 24px;
-            font-weight: bold;
-            margin-bottom: 10px;
-            color: #64ffda;
-            word-wrap: break-word; /* Allow long words to break */
-            overflow-wrap: break-word; /* Alternative property for better browser support */
-        }}
-
-        .card-description {{
-            margin-bottom: 15px;
-        }}
-
-        .card-value {{
-            font-weight: bold;
-            color: #5ccfee;
-        }}
-
-        @media (max-width: 600px) {{
-            .card {{
-                flex-direction: column;
-            }}
-
-            .card-image {{
-                width: 100%;
-                height: auto;
-                max-height: 280px;
-            }}
-
-            .card-name {{
-                font-size: 20px; /* Slightly reduce font size on small screens */
-            }}
-        }}
-
-        .search-container {{
-            text-align: center;
-            margin: 20px 0;
-        }}
-
-        #searchInput {{
-            padding: 10px;
-            width: 300px;
-            border-radius: 5px;
-            border: none;
-            font-size: 16px;
-        }}
-
-        #sortSelect {{
-            padding: 10px;
-            margin-left: 10px;
-            border-radius: 5px;
-            border: none;
-            font-size: 16px;
-            background-color: #112240;
-            color: #e6f1ff;
-        }}
-    </style>
-</head>
-<body>
-    <div class="header">
-        <h2>Total Market Worth: ${:.2}</h2>
-    </div>
-    <h1>One Piece TCG Card List</h1>
-    <div class="search-container">
-        <input type="text" id="searchInput" placeholder="Search card names...">
-        <select id="sortSelect">
-            <option value="default">Sort: Default</option>
-            <option value="market-value">Sort: Market Value</option>
-            <option value="a-z">Sort: A-Z</option>
-        </select>
-    </div>
-    <div class="card-container" id="cardContainer">
-"#, total_worth.0);
-
-let html_code_end = r#"</div>
-<script>
-    const searchInput = document.getElementById('searchInput');
-    const sortSelect = document.getElementById('sortSelect');
-    const cardContainer = document.getElementById('cardContainer');
-    const cards = Array.from(cardContainer.children);
-
-    function sortCards() {
-        const searchTerm = searchInput.value.toLowerCase();
-        const sortOption = sortSelect.value;
-
-        cards.sort((a, b) => {
-            const aName = a.querySelector('.card-name').textContent.toLowerCase();
-            const bName = b.querySelector('.card-name').textContent.toLowerCase();
-            const aMatch = aName.includes(searchTerm);
-            const bMatch = bName.includes(searchTerm);
-            
-            if (aMatch && !bMatch) return -1;
-            if (!aMatch && bMatch) return 1;
-
-            switch (sortOption) {
-                case 'market-value':
-                    const aValue = parseFloat(a.querySelector('.card-value').textContent.replace('Market Value: $', ''));
-                    const bValue = parseFloat(b.querySelector('.card-value').textContent.replace('Market Value: $', ''));
-                    return bValue - aValue;
-                case 'a-z':
-                    return aName.localeCompare(bName);
-                default:
-                    return 0;
+    let data_request = report::gather(&db, webcache.clone(), settings);
+    webcache = data_request.1;
+
+    let data = data_request.0;
+
+    let mut thumbnails: HashMap<String, Vec<u8>> = HashMap::new();
+    let mut slots: Vec<BinderCard> = Vec::new();
+
+    // binders go set by set in card number order, with copies sharing a pocket
+    for set in &data.sets
+    {
+        let mut cards: Vec<&ReportCard> = set.cards.iter().collect();
+        cards.sort_by(|a, b| a.number.cmp(&b.number).then(a.name.cmp(&b.name)));
+
+        for card in cards
+        {
+            if let Some(slot) = slots.iter_mut().find(|slot| slot.product_id == card.product_id)
+            {
+                if slot.market_value != Some(card.market_value)
+                {
+                    slot.market_value = None;
+                }
+
+                slot.quantity += 1;
+                slot.total_value += card.market_value;
+                continue;
             }
-        });
-        
-        cardContainer.innerHTML = '';
-        cards.forEach(card => cardContainer.appendChild(card));
+
+            let image_request = image_src(&card.product_id, settings, &mut thumbnails, webcache.clone());
+            webcache = image_request.1;
+
+            slots.push(BinderCard {
+                product_id: card.product_id.clone(),
+                name: card.product_name.clone(),
+                number: card.number.clone(),
+                set_name: card.set_name.clone(),
+                quantity: 1,
+                market_value: Some(card.market_value),
+                total_value: card.market_value,
+                image: image_request.0
+            });
+        }
     }
 
-    searchInput.addEventListener('input', sortCards);
-    sortSelect.addEventListener('change', sortCards);
+    let pages: Vec<Vec<BinderCard>> = slots.chunks(BINDER_PAGE_SIZE).map(|page| page.to_vec()).collect();
 
-    sortCards();
-</script>
-</body>
-</html>"#;
+    let rendered = render("binder.html", BINDER_TEMPLATE, context! {
+        title => "One Piece TCG Binder Inventory",
+        total_worth => data.total_worth,
+        card_count => data.card_count,
+        price_basis => data.price_basis,
+        unknown_prices => data.unknown_prices,
+        rate_note => currency::rate_note(&data.currency),
+        pages => pages
+    }, &data.currency);
 
-let mut html_code = String::new();
+    if settings.report_mode == ReportMode::Folder && !write_images(path, &thumbnails)
+    {
+        return (Err("could not write the report images!".to_string()), webcache);
+    }
 
-html_code.push_str(&html_code_start);
+    match rendered
+    {
+        Ok(html_code) => (write(path, &html_code), webcache),
+        Err(error) => (Err(format!("could not fill in the binder template! {}", error)), webcache)
+    }
+}
 
-let mut i: u32 = 0;
-for product in &db.cards
+/// writes a diff as a web page to DIFF_LOC
+pub fn generate_diff(diff: &Diff, settings: &Settings) -> Result<(), String>
 {
-    util::clear(webcache.clone());
+    let currency = currency::load(settings);
 
-    let percent = format!("{:.2}%", (i as f64 / db.cards.clone().len() as f64) * 100.0);
-    println!("generating report... {}", percent);
+    let rendered = render("diff.html", DIFF_TEMPLATE, context! {
+        title => "One Piece TCG Collection Changes",
+        theme_css => TemplateValue::from_safe_string(theme_css(settings)),
+        rate_note => currency::rate_note(&currency),
+        diff => diff
+    }, &currency);
 
-    let product_info_request = util::get_product_details(&format_id(product.product_id.clone()), webcache.clone());
+    match rendered
+    {
+        Ok(html_code) => write(DIFF_LOC, &html_code),
+        Err(error) => Err(format!("could not fill in the diff template! {}", error))
+    }
+}
+
+/// the page `serve` uses for adding and removing cards, everything on it comes from the json api
+///
+/// token goes into the page, which sends it back with every change
+pub fn dashboard(settings: &Settings, token: &str) -> Result<String, String>
+{
+    let rendered = render("dashboard.html", DASHBOARD_TEMPLATE, context! {
+        title => "One Piece TCG Collection",
+        token => token,
+        theme_css => TemplateValue::from_safe_string(theme_css(settings))
+    }, &currency::load(settings));
+
+    rendered.map_err(|error| format!("could not fill in the dashboard template! {}", error))
+}
 
-    webcache = product_info_request.1;
+// a finished page going to its file
+fn write(path: &str, html_code: &str) -> Result<(), String>
+{
+    if util::write_file(path, html_code) { Ok(()) } else { Err(format!("could not write {}!", path)) }
+}
 
-    let product_info: Value = serde_json::from_str(product_info_request.0.as_str()).unwrap();
-    let mut product_name = product_info["productName"].as_str().unwrap().to_string();
-    let set_url_name = product_info["setUrlName"].as_str().unwrap();
+fn image_src(product_id: &str, settings: &Settings, thumbnails: &mut HashMap<String, Vec<u8>>, mut webcache: HashMap<String, String>) -> (String, HashMap<String, String>)
+{
+    if settings.report_mode == ReportMode::Cdn
+    {
+        return (format!("{}/product/{}_{}.jpg", util::image_url(), product_id, CDN_IMAGE_SIZE), webcache);
+    }
 
-    let option_op_code = product_info["customAttributes"]["number"].as_str();
+    if !thumbnails.contains_key(product_id)
+    {
+        let image_b64_request = util::card_image_b64(product_id, webcache.clone());
+        webcache = image_b64_request.1;
 
-    let op_code = match option_op_code{
-        None => "",
-        Some(code) => code
-    };
+        thumbnails.insert(product_id.to_string(), images::thumbnail_or_original(&image_b64_request.0));
+    }
 
-    if set_url_name.contains("Pre Release")
+    if settings.report_mode == ReportMode::Embedded
     {
-        product_name = format!("{} {} (Pre Release)", product_name, op_code);
+        (format!("data:image/jpeg;base64,{}", BASE64_STANDARD.encode(&thumbnails[product_id])), webcache)
     }
     else
     {
-        product_name = format!("{} {}", product_name, op_code);
+        (format!("{}/{}.jpg", REPORT_IMAGES, product_id), webcache)
     }
+}
 
-    let product_description_request = product_info["customAttributes"]["description"].as_str();
-    let product_description: String = match product_description_request{
-        None => "No description provided.".to_string(),
-        Some(description) => description.replace("<", " <").replace(">", "> ")
-    };
+// templates named .html get everything put into them escaped
+// values in the templates are dollars, the money filters show them in the chosen currency with its symbol
+fn render(name: &str, default: &str, context: TemplateValue, currency: &Currency) -> Result<String, minijinja::Error>
+{
+    let mut env = Environment::new();
 
-    let market_value_request = product_info["marketPrice"].as_number();
+    let money_currency = currency.clone();
+    env.add_filter("money", move |value: f64| currency::format(&money_currency, value));
 
-    let market_value = match market_value_request{
-        None => Number::from_f64(0.0).unwrap(),
-        Some(num) => num.to_owned()
-    };
+    let signed_currency = currency.clone();
+    env.add_filter("signed", move |value: f64| currency::signed(&signed_currency, value));
+
+    env.add_template_owned(name.to_string(), template(name, default))?;
+    env.get_template(name)?.render(context)
+}
+
+/// where the report goes by default, inside the report folder in folder mode
+pub fn report_path(settings: &Settings) -> &'static str
+{
+    match settings.report_mode
+    {
+        ReportMode::Folder => "report/report.html",
+        _ => "report.html"
+    }
+}
+
+/// where the binder sheets go by default, next to the report
+pub fn binder_path(settings: &Settings) -> &'static str
+{
+    match settings.report_mode
+    {
+        ReportMode::Folder => "report/binder.html",
+        _ => "binder.html"
+    }
+}
 
-    let image_b64_request = util::card_image_b64(&util::format_id(product.product_id.clone()), webcache.clone());
+// the images go next to the page, where its links look for them
+fn write_images(page_path: &str, thumbnails: &HashMap<String, Vec<u8>>) -> bool
+{
+    let dir = Path::new(page_path).parent().unwrap_or(Path::new("")).join(REPORT_IMAGES);
 
-    webcache = image_b64_request.1;
-    
-    let image_b64 = image_b64_request.0;
-    let product_url = format!("https://www.tcgplayer.com/product/{}", format_id(product.product_id.clone()));
+    if std::fs::create_dir_all(&dir).is_err()
+    {
+        return false;
+    }
 
-    html_code.push_str(&generate_card(&product_url, &product_name, market_value.clone(), &image_b64, &product_description));
-    i += 1;
+    thumbnails.iter().all(|(product_id, bytes)| util::write_bytes(&dir.join(format!("{}.jpg", product_id)).to_string_lossy(), bytes))
 }
 
-html_code.push_str(&html_code_end);
-return (util::write_file("report.html", &html_code), webcache.clone());
+/// the themes a report can use, the built in dark and light plus any css file in the config folder's themes folder
+pub fn themes() -> Vec<String>
+{
+    let mut themes = vec!["dark".to_string(), "light".to_string()];
+
+    if let Ok(entries) = std::fs::read_dir(settings::config_dir().join("themes"))
+    {
+        for entry in entries.filter_map(|entry| entry.ok())
+        {
+            let path = entry.path();
+
+            if path.extension().is_some_and(|extension| extension == "css")
+            {
+                let name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+
+                if !themes.contains(&name)
+                {
+                    themes.push(name);
+                }
+            }
+        }
+    }
+
+    themes
 }
 
-fn generate_card(product_url: &str, product_name: &str, market_value: Number, image_b64: &str, product_description: &str) -> String
+// a template of the same name in the config directory's templates folder wins over the built in one
+fn template(name: &str, default: &str) -> String
 {
-    let html_card = format!(r#"<div class="card">
-            <img src="data:image/jpeg;base64,{}" alt="card" class="card-image">
-            <div class="card-info">
-                <a href={} target="_blank"><div class="card-name">{}</div></a>
-                <div class="card-description">{}</div>
-                <div class="card-value">Market Value: ${}</div>
-            </div>
-        </div>"#, image_b64, product_url, product_name, product_description, market_value);
-    
-    return html_card;
-}
\ No newline at end of file
+    let custom = settings::config_dir().join("templates").join(name);
+
+    match std::fs::read_to_string(custom)
+    {
+        Ok(template) => template,
+        Err(_) => default.to_string()
+    }
+}
+
+// themes/<name>.css in the config directory wins over the built in themes
+fn theme_css(settings: &Settings) -> String
+{
+    let custom = settings::config_dir().join("themes").join(format!("{}.css", settings.theme));
+
+    if let Ok(css) = std::fs::read_to_string(custom)
+    {
+        return css;
+    }
+
+    match settings.theme.as_str()
+    {
+        "light" => LIGHT_THEME.to_string(),
+        _ => DARK_THEME.to_string()
+    }
+}
@@ -0,0 +1,35 @@
+//! card images, shrunk down to the size the report actually shows them at
+
+use std::io::Cursor;
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType};
+
+/// the size of a card tile in the report
+pub const THUMBNAIL_WIDTH: u32 = 200;
+/// the same 5:7 shape as the cards themselves, so cropping takes off next to nothing
+pub const THUMBNAIL_HEIGHT: u32 = 280;
+
+/// cropped to fill the tile like the report's object-fit: cover, None if the image can't be read
+pub fn thumbnail(image_b64: &str) -> Option<Vec<u8>>
+{
+    let bytes = BASE64_STANDARD.decode(image_b64).ok()?;
+    let image = image::load_from_memory(&bytes).ok()?;
+
+    let resized = image.resize_to_fill(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT, FilterType::Lanczos3).to_rgb8();
+
+    let mut output = Cursor::new(Vec::new());
+    resized.write_with_encoder(JpegEncoder::new_with_quality(&mut output, 85)).ok()?;
+
+    Some(output.into_inner())
+}
+
+/// falls back to the full size image when it can't be shrunk
+pub fn thumbnail_or_original(image_b64: &str) -> Vec<u8>
+{
+    match thumbnail(image_b64)
+    {
+        Some(bytes) => bytes,
+        None => BASE64_STANDARD.decode(image_b64).unwrap_or_default()
+    }
+}
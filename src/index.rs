@@ -0,0 +1,223 @@
+//! a search over cards we've already looked up, so owned and cached cards can be found without asking tcgplayer
+//!
+//! matching is forgiving about typos and half typed words, eg. "lufy romace" still finds Monkey.D.Luffy from Romance Dawn
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::Value;
+
+use crate::report;
+use crate::util::{self, format_id, Database};
+
+/// the lowest score find keeps, 1.0 being every word matched exactly
+pub const MIN_SCORE: f64 = 0.75;
+
+/// one card the index can find
+#[derive(serde::Serialize, Clone)]
+pub struct Entry
+{
+    /// formatted, eg. "1001"
+    pub product_id: String,
+    /// with the op code and pre release tag, what the menu shows
+    pub name: String,
+    /// eg. "OP01-024"
+    pub number: String,
+    /// eg. "Romance Dawn"
+    pub set_name: String,
+    /// the effect text without html
+    pub description: String,
+    /// copies in the database, in any collection
+    pub owned: u32
+}
+
+/// an entry find turned up
+#[derive(serde::Serialize, Clone)]
+pub struct Match
+{
+    /// the card that matched
+    pub entry: Entry,
+    /// how well it matched, from MIN_SCORE up to 1.0
+    pub score: f64
+}
+
+fn entry(product_info: &Value) -> Option<Entry>
+{
+    let number = product_info["customAttributes"]["number"].as_str().unwrap_or_default();
+
+    if number.is_empty() || product_info["productLineName"].as_str() != Some(util::PRODUCT_LINE)
+    {
+        return None;
+    }
+
+    Some(Entry {
+        product_id: format_id(product_info["productId"].as_number()?.clone()),
+        name: util::display_name(product_info),
+        number: number.to_string(),
+        set_name: util::set_name(product_info),
+        description: report::plain_text(product_info["customAttributes"]["description"].as_str().unwrap_or_default()),
+        owned: 0
+    })
+}
+
+/// every one piece card in the cache's product details and search results, plus every owned card
+///
+/// owned cards the cache doesn't know yet are looked up, everything else comes from the cache as it is
+pub fn build(db: &Database, mut webcache: HashMap<String, String>) -> (Vec<Entry>, HashMap<String, String>)
+{
+    for product_id in owned(db).keys()
+    {
+        webcache = util::get_product_details(product_id, webcache).1;
+    }
+
+    // by product id, product details win over the shorter copies in search results
+    let mut entries: BTreeMap<String, Entry> = BTreeMap::new();
+
+    for (key, body) in &webcache
+    {
+        let products: Vec<Value> = if key.starts_with("get_product_details:")
+        {
+            serde_json::from_str(body).ok().into_iter().collect()
+        }
+        else if key.starts_with("search:")
+        {
+            let response: Value = serde_json::from_str(body).unwrap_or_default();
+            response["results"][0]["results"].as_array().cloned().unwrap_or_default()
+        }
+        else
+        {
+            continue;
+        };
+
+        for product_info in products
+        {
+            let Some(found) = entry(&product_info) else { continue };
+
+            if key.starts_with("get_product_details:") || !entries.contains_key(&found.product_id)
+            {
+                entries.insert(found.product_id.clone(), found);
+            }
+        }
+    }
+
+    let mut entries: Vec<Entry> = entries.into_values().collect();
+    recount(&mut entries, db);
+
+    (entries, webcache)
+}
+
+// copies of each product id in the database
+fn owned(db: &Database) -> HashMap<String, u32>
+{
+    let mut owned: HashMap<String, u32> = HashMap::new();
+
+    for card in &db.cards
+    {
+        *owned.entry(format_id(card.product_id.clone())).or_default() += 1;
+    }
+
+    owned
+}
+
+/// brings the owned counts up to date after the database changes
+pub fn recount(entries: &mut [Entry], db: &Database)
+{
+    let owned = owned(db);
+
+    for entry in entries
+    {
+        entry.owned = owned.get(&entry.product_id).copied().unwrap_or_default();
+    }
+}
+
+/// adds the cards in a new search response to an index from build, so it doesn't have to be built again
+///
+/// cards already in the index are left as they are
+pub fn add_search(entries: &mut Vec<Entry>, body: &str, db: &Database)
+{
+    let response: Value = serde_json::from_str(body).unwrap_or_default();
+    let products = response["results"][0]["results"].as_array().cloned().unwrap_or_default();
+
+    for product_info in products
+    {
+        let Some(found) = entry(&product_info) else { continue };
+
+        if !entries.iter().any(|other| other.product_id == found.product_id)
+        {
+            entries.push(found);
+        }
+    }
+
+    recount(entries, db);
+}
+
+fn words(text: &str) -> Vec<String>
+{
+    text.to_lowercase().split(|character: char| !character.is_alphanumeric() && character != '-').filter(|word| !word.is_empty()).map(|word| word.to_string()).collect()
+}
+
+// how well one typed word fits one word of a card, typos cost a little and a word that's still being typed counts
+fn similarity(typed: &str, word: &str) -> f64
+{
+    if typed == word
+    {
+        return 1.0;
+    }
+
+    if typed.chars().count() >= 3 && word.starts_with(typed)
+    {
+        return 0.95;
+    }
+
+    // one digit off is a different card, so codes and numbers have to be right
+    if typed.chars().any(|character| character.is_ascii_digit())
+    {
+        return 0.0;
+    }
+
+    strsim::normalized_damerau_levenshtein(typed, word).max(strsim::jaro_winkler(typed, word) - 0.1)
+}
+
+/// how well a query fits a card, the average of each typed word's best fit, effect text counting for less
+pub fn score(entry: &Entry, query: &str) -> f64
+{
+    let typed = words(query);
+
+    if typed.is_empty()
+    {
+        return 0.0;
+    }
+
+    // "op01-024" is one word, but "op01" and "024" should find it too
+    let mut fields: Vec<(Vec<String>, f64)> = vec![
+        (words(&entry.name), 1.0),
+        (words(&entry.number), 1.0),
+        (words(&entry.number.replace('-', " ")), 1.0),
+        (words(&entry.set_name), 0.9),
+        (words(&entry.description), 0.8)
+    ];
+
+    fields.retain(|(field, _)| !field.is_empty());
+
+    let total: f64 = typed.iter()
+        .map(|typed_word| {
+            fields.iter()
+                .flat_map(|(field, weight)| field.iter().map(move |word| similarity(typed_word, word) * weight))
+                .fold(0.0, f64::max)
+        })
+        .sum();
+
+    total / typed.len() as f64
+}
+
+/// the cards that fit the query best first, owned cards first when they fit as well
+pub fn find(entries: &[Entry], query: &str) -> Vec<Match>
+{
+    let mut matches: Vec<Match> = entries.iter()
+        .map(|entry| Match { entry: entry.clone(), score: score(entry, query) })
+        .filter(|found| found.score >= MIN_SCORE)
+        .collect();
+
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score).then(b.entry.owned.cmp(&a.entry.owned)).then(a.entry.name.cmp(&b.entry.name)));
+
+    matches
+}
@@ -0,0 +1,282 @@
+//! append-only log of every change to the collection, plus undo/redo for the current session
+
+use std::{collections::BTreeMap, fs::OpenOptions, io::Write};
+
+use chrono::{DateTime, Local};
+
+use crate::trade;
+use crate::util::{self, format_id, Database};
+use crate::valuation::{self, Condition};
+
+/// what kind of change a journal entry records
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq)]
+pub enum Action
+{
+    /// cards came in
+    Add,
+    /// cards left the collection
+    Remove,
+    /// cards changed in place, eg. a new owner or condition
+    Edit,
+    /// cards went to another collection
+    Move,
+    /// a change was taken back
+    Undo,
+    /// an undone change was put back
+    Redo
+}
+
+/// one line of the journal file
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Entry
+{
+    /// when it happened
+    pub date: DateTime<Local>,
+    /// what kind of change it was
+    pub action: Action,
+    /// what changed, eg. "added 2x 1001 to main"
+    pub description: String
+}
+
+/// one undoable change, with the whole database on both sides of it
+pub struct Change
+{
+    /// what changed, as it's written to the journal
+    pub description: String,
+    /// the database before the change
+    pub before: Database,
+    /// the database after the change
+    pub after: Database
+}
+
+/// the changes made this session, newest last
+#[derive(Default)]
+pub struct History
+{
+    /// changes that can be undone
+    pub undo: Vec<Change>,
+    /// undone changes that can be redone, cleared by any new change
+    pub redo: Vec<Change>
+}
+
+/// eg. "add", how history shows the action
+pub fn action_name(action: Action) -> &'static str
+{
+    match action
+    {
+        Action::Add => "add",
+        Action::Remove => "remove",
+        Action::Edit => "edit",
+        Action::Move => "move",
+        Action::Undo => "undo",
+        Action::Redo => "redo"
+    }
+}
+
+/// logs whatever changed between the two databases and makes it undoable, nothing happens if they're the same
+pub fn record(history: &mut History, before: &Database, after: &Database, journal_path: &str) -> bool
+{
+    let (action, description) = match describe(before, after)
+    {
+        None => return false,
+        Some(change) => change
+    };
+
+    append(journal_path, &Entry { date: Local::now(), action, description: description.clone() });
+
+    history.undo.push(Change { description, before: before.clone(), after: after.clone() });
+    history.redo.clear();
+
+    true
+}
+
+/// the database as it was before the last change, None when there's nothing left to undo
+///
+/// stays on current's active collection, undoing a change shouldn't switch what's being looked at
+pub fn undo(history: &mut History, current: &Database, journal_path: &str) -> Option<Database>
+{
+    let change = history.undo.pop()?;
+
+    append(journal_path, &Entry { date: Local::now(), action: Action::Undo, description: change.description.clone() });
+
+    let before = stay_on(change.before.clone(), current);
+    history.redo.push(change);
+
+    Some(before)
+}
+
+/// the database as it was after the last undone change, None when there's nothing to redo
+///
+/// like undo this stays on current's active collection
+pub fn redo(history: &mut History, current: &Database, journal_path: &str) -> Option<Database>
+{
+    let change = history.redo.pop()?;
+
+    append(journal_path, &Entry { date: Local::now(), action: Action::Redo, description: change.description.clone() });
+
+    let after = stay_on(change.after.clone(), current);
+    history.undo.push(change);
+
+    Some(after)
+}
+
+// keeps looking at the same collection, unless the restored database doesn't have it anymore
+fn stay_on(mut restored: Database, current: &Database) -> Database
+{
+    let kept = match &current.active
+    {
+        None => true,
+        Some(name) => restored.collections.contains(name)
+    };
+
+    if kept
+    {
+        restored.active = current.active.clone();
+    }
+
+    restored
+}
+
+/// lines that can't be read are skipped so one bad write doesn't hide the rest of the log
+pub fn entries(journal_path: &str) -> Vec<Entry>
+{
+    util::read_file(journal_path)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Entry>(line).ok())
+        .collect()
+}
+
+fn append(journal_path: &str, entry: &Entry) -> bool
+{
+    let line = match serde_json::to_string(entry)
+    {
+        Ok(line) => line,
+        Err(_) => return false
+    };
+
+    match OpenOptions::new().create(true).append(true).open(journal_path)
+    {
+        Ok(mut file) => writeln!(file, "{}", line).is_ok(),
+        Err(_) => false
+    }
+}
+
+// product id, collection, owner and condition, everything that tells one held card from another
+type Holding = (String, String, String, Condition);
+
+// how many cards there are of each holding
+fn holdings(db: &Database) -> BTreeMap<Holding, i64>
+{
+    let mut holdings: BTreeMap<Holding, i64> = BTreeMap::new();
+
+    for card in &db.cards
+    {
+        *holdings.entry((format_id(card.product_id.clone()), card.collection.clone(), card.owner.clone(), card.condition)).or_insert(0) += 1;
+    }
+
+    holdings
+}
+
+fn describe(before: &Database, after: &Database) -> Option<(Action, String)>
+{
+    // switching the collection being looked at isn't a change to the collection itself
+    let mut before_value = serde_json::to_value(before).ok()?;
+    let mut after_value = serde_json::to_value(after).ok()?;
+    before_value["active"] = serde_json::Value::Null;
+    after_value["active"] = serde_json::Value::Null;
+
+    if before_value == after_value
+    {
+        return None;
+    }
+
+    let mut counts = holdings(after);
+
+    for (key, count) in holdings(before)
+    {
+        *counts.entry(key).or_insert(0) -= count;
+    }
+
+    let mut added: Vec<(Holding, i64)> = counts.iter().filter(|(_, count)| **count > 0).map(|(key, count)| (key.clone(), *count)).collect();
+    let mut removed: Vec<(Holding, i64)> = counts.iter().filter(|(_, count)| **count < 0).map(|(key, count)| (key.clone(), -count)).collect();
+
+    let mut parts: Vec<String> = Vec::new();
+    let mut actions: Vec<Action> = Vec::new();
+
+    // a card that disappeared in one place and showed up in another was moved, handed over or regraded, not added
+    for (from, from_count) in removed.iter_mut()
+    {
+        for (to, to_count) in added.iter_mut()
+        {
+            if from.0 != to.0 || *from_count == 0 || *to_count == 0
+            {
+                continue;
+            }
+
+            let count = (*from_count).min(*to_count);
+            *from_count -= count;
+            *to_count -= count;
+
+            if from.1 != to.1
+            {
+                parts.push(format!("moved {}x {} from {} to {}", count, from.0, from.1, to.1));
+                actions.push(Action::Move);
+            }
+
+            if from.2 != to.2
+            {
+                parts.push(format!("gave {}x {} from {} to {}", count, from.0, trade::owner_name(&from.2), trade::owner_name(&to.2)));
+                actions.push(Action::Edit);
+            }
+
+            if from.3 != to.3
+            {
+                parts.push(format!("changed condition of {}x {} from {} to {}", count, from.0, valuation::condition_name(from.3), valuation::condition_name(to.3)));
+                actions.push(Action::Edit);
+            }
+        }
+    }
+
+    for ((product_id, collection, _, _), count) in added.iter().filter(|(_, count)| *count > 0)
+    {
+        parts.push(format!("added {}x {} to {}", count, product_id, collection));
+        actions.push(Action::Add);
+    }
+
+    for ((product_id, collection, _, _), count) in removed.iter().filter(|(_, count)| *count > 0)
+    {
+        parts.push(format!("removed {}x {} from {}", count, product_id, collection));
+        actions.push(Action::Remove);
+    }
+
+    if before.collections != after.collections
+    {
+        parts.push(format!("collections are now {}", after.collections.join(", ")));
+        actions.push(Action::Edit);
+    }
+
+    if before.owners != after.owners
+    {
+        parts.push(format!("owners are now {}", after.owners.join(", ")));
+        actions.push(Action::Edit);
+    }
+
+    if before.trades.len() != after.trades.len()
+    {
+        parts.push("recorded a trade".to_string());
+        actions.push(Action::Edit);
+    }
+
+    if parts.is_empty()
+    {
+        parts.push("edited the database".to_string());
+    }
+
+    // the most destructive thing that happened names the change
+    let action = [Action::Remove, Action::Add, Action::Move, Action::Edit]
+        .into_iter()
+        .find(|action| actions.contains(action))
+        .unwrap_or(Action::Edit);
+
+    Some((action, parts.join("; ")))
+}
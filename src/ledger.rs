@@ -0,0 +1,142 @@
+//! archive of cards that left the collection, and the money made on them
+
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+
+use crate::util::{format_id, Card, Database};
+
+/// how a card left the collection
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq)]
+pub enum DisposalKind
+{
+    /// for money
+    Sold,
+    /// for other cards
+    Traded,
+    /// lost, given away or just taken out
+    Removed
+}
+
+/// a card that left the collection, kept with what it went for
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Disposal
+{
+    /// the card as it was in the collection
+    pub card: Card,
+    /// the card's name when it left, so the archive reads without the web cache
+    pub name: String,
+    /// how it left
+    pub kind: DisposalKind,
+    /// when it left
+    pub date: NaiveDate,
+    /// for trades this is what the cards received were worth
+    pub sale_price: f64,
+    /// what the marketplace or payment took
+    pub fees: f64,
+    /// what posting it cost
+    pub shipping: f64,
+    /// who it went to, empty when nobody was named
+    pub counterparty: String,
+    /// what the card cost, None when it was never recorded
+    pub purchase_cost: Option<f64>
+}
+
+/// one row of the profit report, a card or a month
+pub struct ProfitLine
+{
+    /// the card or month the row is for
+    pub label: String,
+    /// cards that left in this row
+    pub count: u32,
+    /// what they went for after fees and shipping
+    pub revenue: f64,
+    /// revenue less what the cards cost
+    pub profit: f64,
+    /// some of the cards had no purchase cost, so the profit counts them as free
+    pub missing_cost: bool
+}
+
+/// eg. "sold", as the ledger shows it
+pub fn kind_name(kind: DisposalKind) -> &'static str
+{
+    match kind
+    {
+        DisposalKind::Sold => "sold",
+        DisposalKind::Traded => "traded",
+        DisposalKind::Removed => "removed"
+    }
+}
+
+/// takes the card out of the collection and keeps it in the archive with how it left
+pub fn archive(db: &mut Database, index: usize, disposal: Disposal) -> bool
+{
+    if index >= db.cards.len()
+    {
+        return false;
+    }
+
+    db.cards.remove(index);
+    db.archive.push(disposal);
+
+    true
+}
+
+/// what the card actually brought in, after fees and shipping
+pub fn net(disposal: &Disposal) -> f64
+{
+    disposal.sale_price - disposal.fees - disposal.shipping
+}
+
+/// None for cards that were just removed, they never made or lost anything
+pub fn profit(disposal: &Disposal) -> Option<f64>
+{
+    if disposal.kind == DisposalKind::Removed
+    {
+        return None;
+    }
+
+    Some(net(disposal) - disposal.purchase_cost.unwrap_or(0.0))
+}
+
+/// profit grouped by card, ordered by name
+pub fn by_card(db: &Database) -> Vec<ProfitLine>
+{
+    group(db, |disposal| format!("{} ({})", disposal.name, format_id(disposal.card.product_id.clone())))
+}
+
+/// periods are calendar months, eg. 2024-08
+pub fn by_period(db: &Database) -> Vec<ProfitLine>
+{
+    group(db, |disposal| disposal.date.format("%Y-%m").to_string())
+}
+
+/// profit across every line
+pub fn total(lines: &[ProfitLine]) -> f64
+{
+    lines.iter().map(|line| line.profit).sum()
+}
+
+fn group<F: Fn(&Disposal) -> String>(db: &Database, label: F) -> Vec<ProfitLine>
+{
+    let mut lines: BTreeMap<String, ProfitLine> = BTreeMap::new();
+
+    for disposal in &db.archive
+    {
+        let profit = match profit(disposal)
+        {
+            None => continue,
+            Some(profit) => profit
+        };
+
+        let key = label(disposal);
+        let line = lines.entry(key.clone()).or_insert(ProfitLine { label: key, count: 0, revenue: 0.0, profit: 0.0, missing_cost: false });
+
+        line.count += 1;
+        line.revenue += net(disposal);
+        line.profit += profit;
+        line.missing_cost = line.missing_cost || disposal.purchase_cost.is_none();
+    }
+
+    lines.into_values().collect()
+}
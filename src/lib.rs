@@ -0,0 +1,69 @@
+//! the core of onepiececards, for building bots and scripts on the same collection the program uses
+//!
+//! everything that talks to tcgplayer takes the web cache by value and hands it back with the answer,
+//! so pass it along and save it with [`util::save_cache`] when you're done
+//!
+//! ```no_run
+//! use onepiececards::{report, settings, util};
+//!
+//! let db = util::import("cards.json");
+//! let webcache = util::import_cache("webcache.dat");
+//!
+//! let (data, webcache) = report::gather(&db, webcache, &settings::load());
+//! println!("{} cards worth ${:.2}", data.card_count, data.total_worth);
+//!
+//! util::save_cache(webcache, "webcache.dat");
+//! ```
+
+#![warn(missing_docs)]
+
+/// the database, card lookups on tcgplayer and the web cache
+pub mod util;
+/// named collections (binders) inside one database
+pub mod collection;
+/// owners and trades between them
+pub mod trade;
+/// cards that left the collection and the profit made on them
+pub mod ledger;
+/// the change log and undo/redo
+pub mod journal;
+/// rotating copies of the database
+pub mod backup;
+/// user settings in the config directory
+pub mod settings;
+/// what cards are worth, by price basis and condition
+pub mod valuation;
+/// showing dollar prices in other currencies
+pub mod currency;
+/// the numbers every report shows
+pub mod report;
+/// html reports, binder sheets and the diff page
+pub mod html;
+/// markdown and plain text reports
+pub mod text;
+/// card image thumbnails
+pub mod images;
+/// csv and tcgplayer mass entry exports
+pub mod export;
+/// the collection saved along with its prices
+pub mod snapshot;
+/// what changed between two snapshots
+pub mod diff;
+/// the `serve` web dashboard and json api
+pub mod server;
+/// the full screen terminal ui
+pub mod tui;
+/// a stand-in tcgplayer that replays recorded responses
+pub mod mock;
+/// fuzzy search over cards already looked up
+pub mod index;
+/// cost, power, counter and the rest of a card's game data
+pub mod gamedata;
+/// collection queries on game data
+pub mod query;
+/// counts, value concentration and price movers for the whole collection
+pub mod stats;
+/// playsets, surplus copies and the have list
+pub mod playset;
+/// booster box and pack opening sessions
+pub mod opening;
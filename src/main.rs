@@ -1,18 +1,34 @@
-use std::{collections::HashMap, process::Command, thread, time};
+use std::{collections::HashMap, io::IsTerminal, process::Command, thread, time};
 
 use pause_console::pause_console;
 use serde_json::Value;
 use text_io::read;
-use util::{format_id, pause, save_db, Card, Database};
-
-mod util;
-mod html;
+use chrono::{Local, NaiveDate};
+use onepiececards::{backup, collection, currency, diff, export, gamedata, html, index, journal, ledger, mock, opening, playset, query, report, server, settings, snapshot, stats, text, trade, tui, util, valuation};
+use onepiececards::ledger::{Disposal, DisposalKind};
+use onepiececards::settings::ReportMode;
+use onepiececards::util::{format_id, save_db, Card, Database};
 
 const DB_LOC: &str = "cards.json";
 const CACHE_LOC: &str = "webcache.dat";
+const CSV_LOC: &str = "collection.csv";
+const TCGPLAYER_LOC: &str = "tcgplayer.txt";
+const HAVE_LIST_LOC: &str = "havelist.txt";
+const JOURNAL_LOC: &str = "cards.journal";
+const MARKDOWN_LOC: &str = "report.md";
+const TEXT_LOC: &str = "report.txt";
+const DIFF_TEXT_LOC: &str = "diff.txt";
 
 fn main()
 {
+    let args: Vec<String> = std::env::args().collect();
+
+    if args.len() > 1
+    {
+        command(&args[1..]);
+        return;
+    }
+
     println!("[-] loading webcache");
     thread::sleep(time::Duration::from_millis(300));
     
@@ -50,303 +66,2096 @@ fn main()
     println!("[-] loading card data");
     thread::sleep(time::Duration::from_millis(300));
 
-    let db = util::import(DB_LOC);
+    let db = load_db();
     println!("[+] loaded card data");
     thread::sleep(time::Duration::from_millis(300));
 
     menu(db, webcache);
 }
 
-fn menu(mut db: Database, mut webcache: HashMap<String, String>) -> ()
+// one-shot commands, eg. `onepiececards history`
+fn command(args: &[String])
 {
-    loop
+    match args[0].as_str()
     {
-        util::clear(webcache.clone());
+        "history" => print_history(),
+        "restore" => restore_backup(),
+        "report" => report_command(&args[1..]),
+        "snapshot" => snapshot_command(),
+        "diff" => diff_command(&args[1..]),
+        "serve" => serve_command(&args[1..]),
+        "search" => search_command(&args[1..]),
+        "find" => find_command(&args[1..]),
+        "query" => query_command(&args[1..]),
+        "stats" => stats_command(&args[1..]),
+        "playsets" => playsets_command(&args[1..]),
+        "openings" => openings_command(),
+        "mock" => mock_command(&args[1..], false),
+        "record" => mock_command(&args[1..], true),
+        "tui" => tui_command(),
+        // the menu without the startup questions
+        "classic" => {
+            let (db, webcache) = load_quiet();
+            menu(db, webcache);
+        },
+        _ => println!("[!] unknown command {}! try: tui, classic, history, restore, report, snapshot, diff, serve, search, find, query, stats, playsets, openings, mock, record", args[0])
+    }
+}
 
-        println!("Loading...");
+// the full screen browser, which only has the collection itself, everything else stays in the menu
+fn tui_command()
+{
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal()
+    {
+        println!("[!] the full screen view needs a terminal! run without `tui` for the menu");
+        return;
+    }
 
-        let mut output = String::new();
-        output.push_str("| Pos | Name | ID | Market Price |\n");
+    let (db, webcache) = load_quiet();
+    println!("[-] loading card details");
 
-        let mut i: u32 = 0;
-        for product in db.cards.clone()
-        {
-            util::clear(webcache.clone());
-        
-            let percent = format!("{:.2}%", (i as f64 / db.cards.clone().len() as f64) * 100.0);
-            println!("Loading... {}", percent);
+    let (result, webcache) = tui::run(db, webcache, DB_LOC, JOURNAL_LOC);
+    util::save_cache(webcache, CACHE_LOC);
 
-            let product_util_request = util::get_product_details(&format_id(product.product_id.clone()), webcache.clone());
-            webcache = product_util_request.1;
-            
-            let product_info: Value = serde_json::from_str(product_util_request.0.as_str()).unwrap();
-            let mut product_name = product_info["productName"].as_str().unwrap().to_string();
-            let set_url_name = product_info["setUrlName"].as_str().unwrap();
+    if let Err(error) = result
+    {
+        println!("[debug] [error] {}", error);
+    }
+}
+
+// the database and cache without any of the startup questions, for commands that run on their own
+fn load_quiet() -> (Database, HashMap<String, String>)
+{
+    let db = util::load(DB_LOC).unwrap_or_else(|error| {
+        eprintln!("[!!] Could not import database, using an empty one! {}", error);
+        util::empty_db()
+    });
 
-            let option_op_code = product_info["customAttributes"]["number"].as_str();
+    (db, util::import_cache(CACHE_LOC))
+}
 
-            let op_code = match option_op_code{
-                None => "",
-                Some(code) => code
-            };
+// the database, or an empty one after making sure the user knows saving will replace the one that couldn't be read
+fn load_db() -> Database
+{
+    match util::load(DB_LOC)
+    {
+        Ok(db) => db,
+        Err(error) => {
+            println!("[!!] Could not import database!\n[!!] {}", error);
+            println!("[!!] Using empty database... This will overwrite your current one if you have it saved!");
+            println!("[!!] Close the program and run it with `restore` to bring back a backup instead!");
+            pause_console!();
+            util::empty_db()
+        }
+    }
+}
 
-            if set_url_name.contains("Pre Release")
+fn clear(webcache: HashMap<String, String>, db: &Database)
+{
+    print!("{esc}c", esc = 27 as char);
+    println!("###################");
+    println!("#     card db     #");
+    println!("###################\n");
+    println!("Collection: {}", collection::active_name(db));
+    println!("Cache: {} requests\n", webcache.len());
+}
+
+// the chosen currency, or dollars after saying why there's no rate for it
+fn load_currency(settings: &settings::Settings) -> currency::Currency
+{
+    currency::try_load(settings).unwrap_or_else(|error| {
+        eprintln!("[!] {}", error);
+        currency::usd()
+    })
+}
+
+// the body of a tcgplayer request, empty after saying why it couldn't be fetched
+fn or_report(body: Result<String, String>) -> String
+{
+    body.unwrap_or_else(|error| {
+        println!("[debug] [error] {}", error);
+        String::new()
+    })
+}
+
+fn pause()
+{
+    pause_console!("");
+    pause_console!();
+}
+
+// `report [html|binder|markdown|text] [file] [query]`, markdown and text go to stdout without a file
+fn report_command(args: &[String])
+{
+    let rest = args.get(1..).unwrap_or_default();
+
+    // a file name has to come first, and anything with an operator in it is the query, so a typo in it is reported
+    let (file, query_args) = match rest.first()
+    {
+        Some(first) if !first.contains(query::OPERATOR_CHARACTERS) => (Some(first), &rest[1..]),
+        _ => (None, rest)
+    };
+
+    let clauses = match query::parse(&query_args.join(" "))
+    {
+        Ok(clauses) => clauses,
+        Err(error) => {
+            println!("[!] {}", error);
+            return;
+        }
+    };
+
+    let (db, webcache) = load_quiet();
+    let (db, webcache) = query::filter(&collection::view(&db), &clauses, webcache);
+
+    let format = match args.first().map(|format| format.as_str())
+    {
+        None | Some("markdown") | Some("md") => text::Format::Markdown,
+        Some("text") | Some("txt") => text::Format::Text,
+        Some(page @ ("html" | "binder")) => {
+            let settings = settings::load();
+
+            let (generate_result, report_path) = if page == "binder"
             {
-                product_name = format!("{} {} (Pre Release)", product_name, op_code);
+                let path = file.map(|file| file.as_str()).unwrap_or(html::binder_path(&settings));
+                println!("generating report...");
+                (html::generate_binder(db, webcache, &settings, path), path)
             }
             else
             {
-                product_name = format!("{} {}", product_name, op_code);
-            }
-
-            let market_price_result = product_info["marketPrice"].as_number();
-            let market_price: String = match market_price_result{
-                None => "error".to_string(),
-                Some(price) =>{
-                    let string = format!("${}", price).as_str().to_owned();
-                    string
-                }
+                let path = file.map(|file| file.as_str()).unwrap_or(html::report_path(&settings));
+                println!("generating report...");
+                (html::generate(db, webcache, &settings, path), path)
             };
 
-            output.push_str(format!("| {} | {} | {} | {} |\n", i, product_name, format_id(product.product_id), market_price).as_str());
-            i += 1;
+            util::save_cache(generate_result.1, CACHE_LOC);
+
+            match generate_result.0
+            {
+                Ok(()) => println!("generated {}", report_path),
+                Err(error) => println!("[debug] [error] {}", error)
+            }
+
+            return;
+        },
+        Some(other) => {
+            println!("[!] unknown report format {}! try: html, binder, markdown, text", other);
+            return;
         }
+    };
 
-        util::clear(webcache.clone());
+    let data_request = report::gather(&db, webcache, &settings::load());
+    util::save_cache(data_request.1, CACHE_LOC);
 
-        println!("{}\n", output);
-        println!("[1] add new card [2] remove a card [3] generate card report [4] clear cache [5] quit");
-        let input: i32 = read!();
-        match input
-        {
-            1 => (db, webcache) = add_card(db.clone(), webcache.clone()),
-            2 => (db, webcache) = remove_card(db.clone(), webcache.clone()),
-            3 => (db, webcache) = generate_report(db.clone(), webcache.clone()),
-            4 => webcache = util::clear_cache(CACHE_LOC),
-            5 => quit(db.clone(), webcache.clone()),
-            _ => { pause_console!("Incorrect Option! Hit Enter to try again!"); }
-        };
+    let output = text::render(&data_request.0, format);
+
+    match file
+    {
+        None => print!("{}", output),
+        Some(path) => {
+            if !util::write_file(path, &output)
+            {
+                println!("[debug] [error] could not write {}!", path);
+            }
+        }
     }
 }
 
-fn add_card(mut db: Database, mut webcache: HashMap<String, String>) -> (Database, HashMap<String, String>)
+// `serve [port] [address]`, the address being this computer's on the network to let other devices in
+fn serve_command(args: &[String])
 {
-    util::clear(webcache.clone());
-
-    println!("Input Card Name:");
-    let input: String = read!("\n{}\n");
-    let util_result = util::search(&input, webcache.clone());
-    webcache = util_result.1;
-    let result = util_result.0;
-
-    let request: Value = serde_json::from_str(&result).unwrap();
-    let products = request["results"][0]["results"].as_array().unwrap();
-    
-    util::clear(webcache.clone());
-    println!("Select Correct Card (ID:COUNT) (eg. 0:1 for 1 of 0):");
+    let port = match args.first().map(|port| port.parse::<u16>())
+    {
+        None => server::DEFAULT_PORT,
+        Some(Ok(port)) => port,
+        Some(Err(_)) => {
+            println!("[!] {} is not a port number!", args[0]);
+            return;
+        }
+    };
 
-    let mut i: u32 = 0;
-    let mut hit: bool = false;
+    let address = args.get(1).map(|address| address.as_str()).unwrap_or(server::DEFAULT_ADDRESS);
 
-    for product in products
+    if let Err(error) = server::serve(address, port, load_db(), DB_LOC, CACHE_LOC, JOURNAL_LOC)
     {
-        let product_line_name = product["productLineName"].as_str().unwrap();
-        let option_op_code = product["customAttributes"]["number"].as_str();
-        let set_url_name = product["setUrlName"].as_str().unwrap();
+        println!("[debug] [error] {}", error);
+    }
+}
 
-        let op_code = match option_op_code{
-            None => "",
-            Some(code) => code
-        };
+// `search <name> [set=..] [rarity=..] [color=..] [type=..] [min=..] [max=..] [page=..]`, pages count from 1 here
+fn search_command(args: &[String])
+{
+    let mut words: Vec<&str> = Vec::new();
+    let mut filters = util::SearchFilters::default();
+    let mut page: usize = 0;
 
-        if op_code == "" || product_line_name != "One Piece Card Game"
+    for arg in args
+    {
+        match arg.split_once('=')
         {
-            i += 1;
-            continue;
+            None => words.push(arg),
+            Some(("page", value)) => match value.parse::<usize>()
+            {
+                Ok(number) if number > 0 => page = number - 1,
+                _ => {
+                    println!("[!] {} is not a page number!", value);
+                    return;
+                }
+            },
+            Some((name, value)) => {
+                if !util::SEARCH_FILTER_NAMES.contains(&name)
+                {
+                    println!("[!] {} is not a filter! try: {}=", name, util::SEARCH_FILTER_NAMES.join("=, "));
+                    return;
+                }
+
+                if !util::search_filter(&mut filters, name, value)
+                {
+                    println!("[!] {} is not a price!", value);
+                    return;
+                }
+            }
         }
+    }
+
+    if words.is_empty()
+    {
+        println!("[!] search for what? eg. search luffy set=romance-dawn max=5");
+        return;
+    }
 
-        let name = &product["productName"];
-        let mut name_string = name.as_str().unwrap().to_string();
+    let (_, webcache) = load_quiet();
+    let currency = load_currency(&settings::load());
 
-        if set_url_name.contains("Pre Release")
-        {
-            name_string = format!("{} {} (Pre Release)", name_string, op_code);
-        }
-        else
+    let search_request = util::try_search_page(&words.join(" "), page, &filters, webcache);
+    util::save_cache(search_request.1, CACHE_LOC);
+
+    let response: Value = serde_json::from_str(&or_report(search_request.0)).unwrap_or_default();
+    let pages = util::search_pages(&response);
+
+    let products: Vec<Value> = response["results"][0]["results"].as_array().cloned().unwrap_or_default().into_iter()
+        .filter(|product| product["productLineName"].as_str() == Some(util::PRODUCT_LINE) && !product["customAttributes"]["number"].as_str().unwrap_or_default().is_empty())
+        .collect();
+
+    if products.is_empty()
+    {
+        println!("No results!");
+    }
+
+    for product in &products
+    {
+        let price = match valuation::unit_price(product, valuation::PriceBasis::Market)
         {
-            name_string = format!("{} {}", name_string, op_code);
-        }
+            None => "unknown".to_string(),
+            Some(price) => currency::format(&currency, price)
+        };
 
-        println!("[{}] {}", i, name_string);
-        hit = true;
-        i += 1;
+        println!("{} | {} | {} | {} | {}", product["productId"], util::display_name(product), util::set_name(product), product["rarityName"].as_str().unwrap_or_default(), price);
     }
-    
-    if !hit
+
+    if pages > 1
     {
-        println!("No results! Try searching the name in a different way!");
-        pause_console::pause_console!();
-        return (db, webcache);
+        println!("\npage {} of {}", page + 1, pages);
     }
+}
 
-    print!("\nSelection (ID:COUNT) (eg. 0:1 for 1 of 0): ");
-    
-    let selection_string: String = read!("{}\n");
+// `find <name>`, searches the cards already looked up without going to tcgplayer
+fn find_command(args: &[String])
+{
+    if args.is_empty()
+    {
+        println!("[!] find what? eg. find lufy op01");
+        return;
+    }
 
-    let selection_vec = selection_string.trim().split(':').collect::<Vec<&str>>();
+    let (db, webcache) = load_quiet();
 
-    let selection_attempt = selection_vec[0].parse::<usize>();
+    let index_request = index::build(&db, webcache);
+    util::save_cache(index_request.1, CACHE_LOC);
 
-    let selection = match selection_attempt
+    let matches = index::find(&index_request.0, &args.join(" "));
+
+    if matches.is_empty()
     {
-        Ok(usize) => usize,
-        Err(_) => { 
-            println!("Incorrect Selection Format!");  
+        println!("Nothing we've seen fits! Try `search` to ask tcgplayer");
+    }
 
-            let error: usize = usize::MAX;
+    for found in matches
+    {
+        println!("{} | {} | {} | owned {}", found.entry.product_id, found.entry.name, found.entry.set_name, found.entry.owned);
+    }
+}
 
-            error
+// `stats [text|markdown|json]`
+fn stats_command(args: &[String])
+{
+    let format = match args.first().map(|format| format.as_str())
+    {
+        None | Some("text") | Some("txt") => Some(text::Format::Text),
+        Some("markdown") | Some("md") => Some(text::Format::Markdown),
+        Some("json") => None,
+        Some(other) => {
+            println!("[!] unknown stats format {}! try: text, markdown, json", other);
+            return;
         }
     };
-    
-    if selection == usize::MAX
+
+    let (db, webcache) = load_quiet();
+    let stats_request = stats::gather(&collection::view(&db), webcache, &settings::load());
+    util::save_cache(stats_request.1, CACHE_LOC);
+
+    match format
     {
-        pause_console::pause_console!();
-        return (db, webcache);
+        Some(format) => print!("{}", stats::render(&stats_request.0, format)),
+        None => println!("{}", serde_json::to_string_pretty(&stats_request.0).unwrap_or_default())
     }
+}
 
-    let count_attempt = selection_vec[1].parse::<u32>();
+// `playsets` lists surplus and missing copies, `playsets have [file]` prints or saves the have list
+fn playsets_command(args: &[String])
+{
+    let (db, webcache) = load_quiet();
+    let settings = settings::load();
 
-    let count = match count_attempt
+    match args.first().map(|arg| arg.as_str())
     {
-        Ok(u32) => u32,
-        Err(_) => { 
-            println!("Incorrect Count Format!");  
+        None => {
+            let analysis_request = playset::analyse(&collection::view(&db), webcache, &settings);
+            util::save_cache(analysis_request.1, CACHE_LOC);
 
-            let error: u32 = u32::MAX;
+            print!("{}", playset::text(&analysis_request.0, &load_currency(&settings)));
+        },
+        Some("have") => {
+            let have_request = playset::have_list(&collection::view(&db), webcache, &settings);
+            util::save_cache(have_request.1, CACHE_LOC);
 
-            error
+            match args.get(1)
+            {
+                None => print!("{}", have_request.0),
+                Some(path) => {
+                    if util::write_file(path, &have_request.0)
+                    {
+                        println!("saved {}", path);
+                    }
+                    else
+                    {
+                        println!("[debug] [error] could not write {}!", path);
+                    }
+                }
+            }
+        },
+        Some(other) => println!("[!] unknown playsets option {}! try: playsets, playsets have [file]", other)
+    }
+}
+
+// `openings`, every booster session's cost against what came out of it
+fn openings_command()
+{
+    let (db, webcache) = load_quiet();
+
+    let summary_request = opening::summary(&db, webcache);
+    util::save_cache(summary_request.1, CACHE_LOC);
+
+    print!("{}", opening::text(&summary_request.0, &load_currency(&settings::load())));
+}
+
+// `query <query>`, eg. query color=red subtype=straw hat crew counter=2000
+fn query_command(args: &[String])
+{
+    let clauses = match query::parse(&args.join(" "))
+    {
+        Ok(clauses) if !clauses.is_empty() => clauses,
+        Ok(_) => {
+            println!("[!] query what? eg. query cost=5 type=character (fields: {})", query::FIELDS.join(", "));
+            return;
+        },
+        Err(error) => {
+            println!("[!] {}", error);
+            return;
         }
     };
-    
-    if count == u32::MAX
+
+    let (db, webcache) = load_quiet();
+    let settings = settings::load();
+    let currency = load_currency(&settings);
+
+    let (matching, mut webcache) = query::filter(&collection::view(&db), &clauses, webcache);
+
+    // copies of the same product share a line
+    let mut counts: Vec<(String, u32)> = Vec::new();
+
+    for card in &matching.cards
     {
-        pause_console::pause_console!();
-        return (db, webcache);
+        let product_id = format_id(card.product_id.clone());
+
+        match counts.iter_mut().find(|(counted, _)| *counted == product_id)
+        {
+            Some((_, count)) => *count += 1,
+            None => counts.push((product_id, 1))
+        }
     }
 
-    let mut j: u32 = 0;
+    let mut lines: Vec<String> = Vec::new();
 
-    while j < count
+    for (product_id, count) in counts
     {
-        db.cards.push(Card { product_id: products[selection]["productId"].as_number().unwrap().clone() });
-        j += 1;
+        let lookup_request = gamedata::lookup(&product_id, webcache);
+        let price_request = valuation::price(&product_id, settings.price_basis, lookup_request.1);
+        webcache = price_request.1;
+
+        let card = lookup_request.0;
+        let price = price_request.0.map(|price| currency::format(&currency, price)).unwrap_or("unknown".to_string());
+
+        lines.push(format!("{}x {} | {} | {} | {}", count, card.name, gamedata::summary(&card), card.set_name, price));
     }
 
-    let save_result = save_db(db.clone(), DB_LOC);
+    lines.sort_by_key(|line| line.split_once(' ').map(|(_, rest)| rest.to_string()).unwrap_or_default());
 
-    if !save_result
+    for line in &lines
     {
-        println!("[debug] [error] Database could not be saved!");
-        pause();
+        println!("{}", line);
     }
 
-    (db, webcache)
+    println!("\n{} cards match {}", matching.cards.len(), query::describe(&clauses));
+
+    util::save_cache(webcache, CACHE_LOC);
 }
 
-fn remove_card(mut db: Database, mut webcache: HashMap<String, String>) -> (Database, HashMap<String, String>)
+// `mock [port] [fixtures]` and `record [port] [fixtures]`
+fn mock_command(args: &[String], record: bool)
 {
-    print!("Selection: ");
-    
-    let selection: usize = read!();
+    let port = match args.first().map(|port| port.parse::<u16>())
+    {
+        None => mock::DEFAULT_PORT,
+        Some(Ok(port)) => port,
+        Some(Err(_)) => {
+            println!("[!] {} is not a port number!", args[0]);
+            return;
+        }
+    };
 
-    let product_info_util_request = util::get_product_details(&format_id(db.cards[selection].product_id.clone()), webcache.clone());
-    webcache = product_info_util_request.1;
+    let fixtures = args.get(1).map(|fixtures| fixtures.as_str()).unwrap_or(mock::DEFAULT_FIXTURES);
 
-    let product_info: Value = serde_json::from_str(product_info_util_request.0.as_str()).unwrap();
-    let mut product_name = product_info["productName"].as_str().unwrap().to_string();
-    let set_url_name = product_info["setUrlName"].as_str().unwrap();
+    if let Err(error) = mock::serve(std::path::Path::new(fixtures), port, record)
+    {
+        println!("[debug] [error] {}", error);
+    }
+}
 
-    let option_op_code = product_info["customAttributes"]["number"].as_str();
+fn snapshot_command()
+{
+    let (db, webcache) = load_quiet();
 
-    let op_code = match option_op_code{
-        None => "",
-        Some(code) => code
-    };
+    let snapshot_request = snapshot::create(&db, webcache, &settings::load());
+    util::save_cache(snapshot_request.1, CACHE_LOC);
 
-    if set_url_name.contains("Pre Release")
+    match snapshot_request.0
     {
-        product_name = format!("{} {} (Pre Release)", product_name, op_code);
+        Some(path) => println!("saved {}", path.display()),
+        None => println!("[debug] [error] could not save a snapshot!")
     }
-    else
+}
+
+// `diff` lists what can be compared, `diff <old> [new] [html]` takes numbers from that list, `current` or a file, `html` anywhere asks for the web page
+fn diff_command(args: &[String])
+{
+    let sources = snapshot::sources(DB_LOC);
+
+    if args.is_empty()
+    {
+        println!("[current] the collection right now");
+
+        for (i, source) in sources.iter().enumerate()
+        {
+            println!("[{}] {}", i, source.label);
+        }
+
+        return;
+    }
+
+    // the format keyword comes out first so `diff 0 html` compares with the collection right now
+    let html = args.iter().any(|arg| arg == "html");
+    let snapshots: Vec<&str> = args.iter().map(|arg| arg.as_str()).filter(|arg| *arg != "html").collect();
+
+    if snapshots.is_empty()
     {
-        product_name = format!("{} {}", product_name, op_code);
+        println!("[!] pick a snapshot to compare! run `diff` to see what can be compared");
+        return;
     }
 
-    println!("Are you sure you want to delete {}? (y/n)", product_name);
+    let (db, webcache) = load_quiet();
+    let settings = settings::load();
 
-    let confirmation: char = read!();
+    let old_request = pick_snapshot(snapshots[0], &sources, &db, webcache, &settings);
+    let new_request = pick_snapshot(snapshots.get(1).copied().unwrap_or("current"), &sources, &db, old_request.1, &settings);
+    let mut webcache = new_request.1;
 
-    if confirmation == 'y'
+    let ((old, old_label), (new, new_label)) = match (old_request.0, new_request.0)
     {
-        db.cards.remove(selection);
+        (Some(old), Some(new)) => (old, new),
+        _ => {
+            util::save_cache(webcache, CACHE_LOC);
+            println!("[!] could not find that snapshot! run `diff` to see what can be compared");
+            return;
+        }
+    };
 
-        let save_result = save_db(db.clone(), DB_LOC);
+    let diff_request = diff::compare(&old, &new, &old_label, &new_label, webcache, &settings);
+    webcache = diff_request.1;
 
-        if !save_result
+    util::save_cache(webcache, CACHE_LOC);
+
+    if html
+    {
+        match html::generate_diff(&diff_request.0, &settings)
         {
-            println!("[debug] [error] Database could not be saved!");
-            pause();
+            Ok(()) => println!("generated {}", html::DIFF_LOC),
+            Err(error) => println!("[debug] [error] {}", error)
         }
     }
-
-    (db, webcache)
+    else
+    {
+        print!("{}", diff::text(&diff_request.0, &load_currency(&settings)));
+    }
 }
 
-fn quit(_db: Database, webcache: HashMap<String, String>)
+// a number from the source list, `current`, or the path of a snapshot or backup
+fn pick_snapshot(choice: &str, sources: &[snapshot::Source], db: &Database, webcache: HashMap<String, String>, settings: &settings::Settings) -> (Option<(snapshot::Snapshot, String)>, HashMap<String, String>)
 {
-    util::save_cache(webcache, CACHE_LOC);
-    std::process::exit(0);
+    if choice == "current"
+    {
+        let current_request = snapshot::current(db, webcache, settings);
+        return (Some((current_request.0, "now".to_string())), current_request.1);
+    }
+
+    let (path, label) = match choice.parse::<usize>().ok().and_then(|index| sources.get(index))
+    {
+        Some(source) => (source.path.clone(), source.label.clone()),
+        None => (std::path::PathBuf::from(choice), choice.to_string())
+    };
+
+    (snapshot::load(&path).map(|found| (found, label)), webcache)
 }
 
-fn generate_report(db: Database, mut webcache: HashMap<String, String>) -> (Database, HashMap<String, String>)
+fn menu(mut db: Database, mut webcache: HashMap<String, String>)
 {
-    let generate_result = html::generate(db.clone(), webcache.clone());
+    let mut history = journal::History::default();
 
-    webcache = generate_result.1;
+    loop
+    {
+        clear(webcache.clone(), &db);
 
-    let result = generate_result.0;
+        println!("Loading...");
 
-    util::clear(webcache.clone());
+        let settings = settings::load();
+        let currency = load_currency(&settings);
 
-    if result
-    {
-        util::save_cache(webcache.clone(), CACHE_LOC);
-        println!("generated a report! open report.html? (y/n)");
+        let mut output = String::new();
+        output.push_str("| Pos | Name | ID | Collection | Owner | Condition | Price |\n");
 
-        let confirmation: char = read!();
+        let indices = collection::view_indices(&db);
+        let mut total_worth: f64 = 0.0;
+        let mut unknown_prices: u32 = 0;
 
-        if confirmation == 'y'
+        for (i, index) in indices.iter().enumerate()
         {
-            let _ = if cfg!(target_os = "windows") {
-                Command::new("cmd")
-                    .args(["/C", "start report.html"])
-                    .spawn()
-                    .unwrap()
-            } else {
+            let product = db.cards[*index].clone();
+
+            clear(webcache.clone(), &db);
+        
+            let percent = format!("{:.2}%", (i as f64 / indices.len() as f64) * 100.0);
+            println!("Loading... {}", percent);
+
+            let product_util_request = util::try_get_product_details(&format_id(product.product_id.clone()), webcache.clone());
+            webcache = product_util_request.1;
+            
+            let product_info: Value = serde_json::from_str(&or_report(product_util_request.0)).unwrap_or_default();
+            let product_name = util::display_name(&product_info);
+
+            let price_result = valuation::unit_price(&product_info, settings.price_basis);
+            let price: String = match price_result{
+                None => {
+                    unknown_prices += 1;
+                    "unknown".to_string()
+                },
+                Some(price) =>{
+                    let value = price * valuation::multiplier(product.condition, &settings);
+                    total_worth += value;
+                    currency::format(&currency, value)
+                }
+            };
+
+            output.push_str(format!("| {} | {} | {} | {} | {} | {} | {} |\n", i, product_name, format_id(product.product_id), product.collection, trade::owner_name(&product.owner), valuation::condition_name(product.condition), price).as_str());
+        }
+
+        output.push_str(format!("\nTotal Worth ({}): {}", valuation::basis_name(settings.price_basis), currency::format(&currency, total_worth)).as_str());
+
+        if unknown_prices > 0
+        {
+            output.push_str(format!("\n{} cards have no price and count as {}", unknown_prices, currency::format(&currency, 0.0)).as_str());
+        }
+
+        if currency.date.is_some()
+        {
+            output.push_str(format!("\n{}", currency::rate_note(&currency)).as_str());
+        }
+
+        clear(webcache.clone(), &db);
+
+        println!("{}\n", output);
+        println!("[1] add new card [2] remove a card [3] generate card report [4] export collection [5] collections [6] owners and trades [7] sales ledger [8] undo [9] redo [10] history [11] settings [12] clear cache [13] open booster packs [14] quit");
+        let input: i32 = read!();
+
+        let before = db.clone();
+
+        match input
+        {
+            1 => (db, webcache) = add_card(db.clone(), webcache.clone()),
+            2 => (db, webcache) = remove_card(db.clone(), webcache.clone()),
+            3 => (db, webcache) = generate_report(db.clone(), webcache.clone()),
+            4 => (db, webcache) = export_collection(db.clone(), webcache.clone()),
+            5 => db = collections(db.clone(), webcache.clone()),
+            6 => (db, webcache) = trades(db.clone(), webcache.clone()),
+            7 => sales_ledger(&db, webcache.clone()),
+            8 => {
+                db = undo_redo(db.clone(), journal::undo(&mut history, &db, JOURNAL_LOC), "undo");
+                continue;
+            },
+            9 => {
+                db = undo_redo(db.clone(), journal::redo(&mut history, &db, JOURNAL_LOC), "redo");
+                continue;
+            },
+            10 => {
+                print_history();
+                pause();
+            },
+            11 => change_settings(&db, webcache.clone()),
+            12 => webcache = util::clear_cache(CACHE_LOC),
+            13 => (db, webcache) = booster_packs(db.clone(), webcache.clone()),
+            14 => quit(db.clone(), webcache.clone()),
+            _ => { pause_console!("Incorrect Option! Hit Enter to try again!"); }
+        };
+
+        journal::record(&mut history, &before, &db, JOURNAL_LOC);
+    }
+}
+
+fn undo_redo(db: Database, restored: Option<Database>, action: &str) -> Database
+{
+    let restored = match restored
+    {
+        None => {
+            pause_console!("Nothing to {}! Hit Enter to go back!", action);
+            return db;
+        },
+        Some(restored) => restored
+    };
+
+    let save_result = save_db(restored.clone(), DB_LOC);
+
+    if !save_result
+    {
+        println!("[debug] [error] Database could not be saved!");
+        pause();
+    }
+
+    restored
+}
+
+fn restore_backup()
+{
+    let backups = backup::list(DB_LOC);
+
+    if backups.is_empty()
+    {
+        println!("No backups yet!");
+        return;
+    }
+
+    for (i, backup) in backups.iter().enumerate()
+    {
+        let cards = match backup::load(backup)
+        {
+            None => "unreadable".to_string(),
+            Some(db) => format!("{} cards", db.cards.len())
+        };
+
+        println!("[{}] {} ({})", i, backup.date.format("%Y-%m-%d %H:%M:%S"), cards);
+    }
+
+    print!("\nRestore which backup? ");
+    let selection: usize = read!();
+
+    let chosen = match backups.get(selection)
+    {
+        None => {
+            println!("No backup at that position!");
+            return;
+        },
+        Some(chosen) => chosen
+    };
+
+    println!("This replaces {} with the backup from {}. Are you sure? (y/n)", DB_LOC, chosen.date.format("%Y-%m-%d %H:%M:%S"));
+    let confirmation: char = read!();
+
+    if confirmation != 'y'
+    {
+        return;
+    }
+
+    // a database that can't be read is usually why a backup is being restored, so the history starts from nothing
+    let before = util::load(DB_LOC).unwrap_or_else(|error| {
+        println!("[!!] Could not import the current database, the history will show the whole backup as added! {}", error);
+        util::empty_db()
+    });
+
+    let after = match backup::restore(DB_LOC, chosen)
+    {
+        None => {
+            println!("[debug] [error] could not restore that backup!");
+            return;
+        },
+        Some(after) => after
+    };
+
+    journal::record(&mut journal::History::default(), &before, &after, JOURNAL_LOC);
+
+    println!("restored!");
+}
+
+fn print_history()
+{
+    let entries = journal::entries(JOURNAL_LOC);
+
+    if entries.is_empty()
+    {
+        println!("No changes recorded yet!");
+    }
+
+    for entry in entries
+    {
+        println!("{} [{}] {}", entry.date.format("%Y-%m-%d %H:%M:%S"), journal::action_name(entry.action), entry.description);
+    }
+}
+
+fn add_card(mut db: Database, mut webcache: HashMap<String, String>) -> (Database, HashMap<String, String>)
+{
+    clear(webcache.clone(), &db);
+
+    println!("Input Card Name:");
+    let input: String = read!("\n{}\n");
+    let mut page: usize = 0;
+    let mut filters = util::SearchFilters::default();
+
+    // shows a page of results until something other than next page, previous page or filters is picked
+    let (products, selection_string) = loop
+    {
+        let util_result = util::try_search_page(&input, page, &filters, webcache.clone());
+        webcache = util_result.1;
+
+        // a failed search reads as no results, with why under the header
+        let request: Value = serde_json::from_str(util_result.0.as_deref().unwrap_or_default()).unwrap_or_default();
+        let products = request["results"][0]["results"].as_array().cloned().unwrap_or_default();
+        let pages = util::search_pages(&request);
+
+        clear(webcache.clone(), &db);
+        or_report(util_result.0);
+        println!("Select Correct Card (ID:COUNT) (eg. 0:1 for 1 of 0):");
+
+        let mut i: u32 = 0;
+        let mut hit: bool = false;
+
+        for product in &products
+        {
+            let product_line_name = product["productLineName"].as_str().unwrap_or_default();
+            let op_code = product["customAttributes"]["number"].as_str().unwrap_or_default();
+
+            if op_code.is_empty() || product_line_name != util::PRODUCT_LINE
+            {
+                i += 1;
+                continue;
+            }
+
+            println!("[{}] {}", i, util::display_name(product));
+            hit = true;
+            i += 1;
+        }
+
+        let has_next = page + 1 < pages;
+        let has_previous = page > 0;
+
+        let filtered = filters != util::SearchFilters::default();
+
+        if !hit && !has_next && !has_previous && !filtered
+        {
+            println!("No results! Try searching the name in a different way!");
+            pause_console::pause_console!();
+            return (db, webcache);
+        }
+
+        if !hit
+        {
+            println!("No one piece cards on this page!");
+        }
+
+        if filtered
+        {
+            println!("\nFilters: {}", util::describe_filters(&filters));
+        }
+
+        if pages > 1
+        {
+            println!("\nPage {} of {}", page + 1, pages);
+        }
+
+        if has_next
+        {
+            println!("[n] next page");
+        }
+
+        if has_previous
+        {
+            println!("[p] previous page");
+        }
+
+        println!("[f] filter by set, rarity, color, type or price");
+
+        print!("\nSelection (ID:COUNT) (eg. 0:1 for 1 of 0): ");
+
+        let selection_string: String = read!("{}\n");
+
+        match selection_string.trim()
+        {
+            "n" if has_next => page += 1,
+            "p" if has_previous => page -= 1,
+            "f" => {
+                filters = pick_filters(filters);
+                page = 0;
+            },
+            _ => break (products, selection_string)
+        }
+    };
+
+    let selection_vec = selection_string.trim().split(':').collect::<Vec<&str>>();
+
+    let selection_attempt = selection_vec[0].parse::<usize>();
+
+    let selection = match selection_attempt
+    {
+        Ok(usize) => usize,
+        Err(_) => { 
+            println!("Incorrect Selection Format!");  
+
+            let error: usize = usize::MAX;
+
+            error
+        }
+    };
+    
+    if selection == usize::MAX
+    {
+        pause_console::pause_console!();
+        return (db, webcache);
+    }
+
+    let count_attempt = selection_vec.get(1).unwrap_or(&"").parse::<u32>();
+
+    let count = match count_attempt
+    {
+        Ok(u32) => u32,
+        Err(_) => { 
+            println!("Incorrect Count Format!");  
+
+            let error: u32 = u32::MAX;
+
+            error
+        }
+    };
+    
+    if count == u32::MAX
+    {
+        pause_console::pause_console!();
+        return (db, webcache);
+    }
+
+    let product_id = match products.get(selection).and_then(|product| product["productId"].as_number())
+    {
+        Some(product_id) => product_id.clone(),
+        None => {
+            println!("No card at that position!");
+            pause_console::pause_console!();
+            return (db, webcache);
+        }
+    };
+
+    let mut owner = String::new();
+
+    if !db.owners.is_empty()
+    {
+        for (i, name) in db.owners.iter().enumerate()
+        {
+            println!("[{}] {}", i, name);
+        }
+
+        print!("Owner (blank for nobody): ");
+        let owner_input: String = read!("{}\n");
+
+        if let Ok(owner_index) = owner_input.trim().parse::<usize>()
+        {
+            owner = db.owners.get(owner_index).cloned().unwrap_or_default();
+        }
+    }
+
+    for (i, condition) in valuation::CONDITIONS.iter().enumerate()
+    {
+        println!("[{}] {}", i, valuation::condition_name(*condition));
+    }
+
+    print!("Condition (blank for near mint): ");
+    let condition_input: String = read!("{}\n");
+
+    let condition = condition_input.trim().parse::<usize>().ok().and_then(|pos| valuation::CONDITIONS.get(pos).copied()).unwrap_or_default();
+
+    let mut j: u32 = 0;
+
+    while j < count
+    {
+        db.cards.push(Card { product_id: product_id.clone(), collection: collection::target(&db), owner: owner.clone(), condition });
+        j += 1;
+    }
+
+    let save_result = save_db(db.clone(), DB_LOC);
+
+    if !save_result
+    {
+        println!("[debug] [error] Database could not be saved!");
+        pause();
+    }
+
+    (db, webcache)
+}
+
+// asks for each search filter in turn, keeping what's there on a blank answer and clearing it on "-"
+fn pick_filters(mut filters: util::SearchFilters) -> util::SearchFilters
+{
+    let prompts = [("set", "Set (eg. romance-dawn)"), ("rarity", "Rarity (eg. Super Rare)"), ("color", "Color"), ("type", "Card type (eg. Leader)"), ("min", "Lowest price"), ("max", "Highest price")];
+
+    for (name, prompt) in prompts
+    {
+        print!("{} (blank to keep, - for any): ", prompt);
+        let input: String = read!("{}\n");
+
+        let value = match input.trim()
+        {
+            "" => continue,
+            "-" => "",
+            value => value
+        };
+
+        if !util::search_filter(&mut filters, name, value)
+        {
+            println!("{} is not a price, leaving it as it was!", value);
+        }
+    }
+
+    filters
+}
+
+// the menu position of an owned card picked by name, asking which copy when more than one fits
+fn find_position(db: &Database, query: &str, webcache: HashMap<String, String>) -> (Option<usize>, HashMap<String, String>)
+{
+    let index_request = index::build(db, webcache);
+    let webcache = index_request.1;
+
+    let indices = collection::view_indices(db);
+    let mut positions: Vec<usize> = Vec::new();
+
+    for found in index::find(&index_request.0, query)
+    {
+        for (pos, card_index) in indices.iter().enumerate()
+        {
+            let card = &db.cards[*card_index];
+
+            if format_id(card.product_id.clone()) == found.entry.product_id
+            {
+                println!("[{}] {} | {} | {} | {}", pos, found.entry.name, card.collection, trade::owner_name(&card.owner), valuation::condition_name(card.condition));
+                positions.push(pos);
+            }
+        }
+    }
+
+    match positions.len()
+    {
+        0 => {
+            println!("No owned card fits {}!", query);
+            (None, webcache)
+        },
+        1 => (Some(positions[0]), webcache),
+        _ => {
+            print!("Selection: ");
+            let input: String = read!("{}\n");
+
+            (input.trim().parse::<usize>().ok(), webcache)
+        }
+    }
+}
+
+fn remove_card(mut db: Database, mut webcache: HashMap<String, String>) -> (Database, HashMap<String, String>)
+{
+    print!("Selection (position or name): ");
+
+    let input: String = read!("{}\n");
+
+    let pos = match input.trim().parse::<usize>()
+    {
+        Ok(pos) => Some(pos),
+        Err(_) => {
+            let find_request = find_position(&db, input.trim(), webcache);
+            webcache = find_request.1;
+            find_request.0
+        }
+    };
+
+    let selection = match pos.and_then(|pos| collection::view_indices(&db).get(pos).copied())
+    {
+        None => {
+            pause_console!("No card at that position! Hit Enter to go back!");
+            return (db, webcache);
+        },
+        Some(index) => index
+    };
+
+    let product_info_util_request = util::try_get_product_details(&format_id(db.cards[selection].product_id.clone()), webcache.clone());
+    webcache = product_info_util_request.1;
+
+    let product_info: Value = serde_json::from_str(&or_report(product_info_util_request.0)).unwrap_or_default();
+    let product_name = util::display_name(&product_info);
+
+    println!("Are you sure you want to delete {}? (y/n)", product_name);
+
+    let confirmation: char = read!();
+
+    if confirmation == 'y'
+    {
+        println!("[1] sold [2] traded away [3] just remove");
+        let kind = match read!()
+        {
+            1 => DisposalKind::Sold,
+            2 => DisposalKind::Traded,
+            _ => DisposalKind::Removed
+        };
+
+        let mut disposal = Disposal {
+            card: db.cards[selection].clone(),
+            name: product_name,
+            kind,
+            date: Local::now().date_naive(),
+            sale_price: 0.0,
+            fees: 0.0,
+            shipping: 0.0,
+            counterparty: String::new(),
+            purchase_cost: None
+        };
+
+        if kind != DisposalKind::Removed
+        {
+            print!("Date (YYYY-MM-DD, blank for today): ");
+            let date: String = read!("{}\n");
+
+            if let Ok(parsed) = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+            {
+                disposal.date = parsed;
+            }
+
+            let currency = load_currency(&settings::load());
+
+            disposal.sale_price = read_money("Sale Price (or value received): ", &currency).unwrap_or(0.0);
+            disposal.fees = read_money("Fees: ", &currency).unwrap_or(0.0);
+            disposal.shipping = read_money("Shipping: ", &currency).unwrap_or(0.0);
+
+            print!("Sold/Traded To: ");
+            let counterparty: String = read!("{}\n");
+            disposal.counterparty = counterparty.trim().to_string();
+
+            disposal.purchase_cost = read_money("What you paid for it (blank if unknown): ", &currency);
+        }
+
+        ledger::archive(&mut db, selection, disposal);
+
+        let save_result = save_db(db.clone(), DB_LOC);
+
+        if !save_result
+        {
+            println!("[debug] [error] Database could not be saved!");
+            pause();
+        }
+    }
+
+    (db, webcache)
+}
+
+fn quit(_db: Database, webcache: HashMap<String, String>)
+{
+    util::save_cache(webcache, CACHE_LOC);
+    std::process::exit(0);
+}
+
+fn generate_report(db: Database, mut webcache: HashMap<String, String>) -> (Database, HashMap<String, String>)
+{
+    clear(webcache.clone(), &db);
+
+    println!("[1] web page (html) [2] markdown [3] plain text [4] printable binder sheets [5] what changed (diff) [6] take a price snapshot [7] collection stats");
+    let input: i32 = read!();
+
+    let clauses = match input
+    {
+        1..=4 => match ask_query()
+        {
+            Some(clauses) => clauses,
+            None => return (db, webcache)
+        },
+        _ => Vec::new()
+    };
+
+    let format = match input
+    {
+        1 => return generate_html_report(db, webcache, false, &clauses),
+        4 => return generate_html_report(db, webcache, true, &clauses),
+        5 => return diff_report(db, webcache),
+        6 => {
+            let snapshot_request = snapshot::create(&db, webcache, &settings::load());
+            webcache = snapshot_request.1;
+
+            match snapshot_request.0
+            {
+                Some(path) => println!("saved {}!", path.display()),
+                None => println!("[debug] [error] could not save a snapshot!")
+            }
+
+            pause();
+            return (db, webcache);
+        },
+        7 => {
+            clear(webcache.clone(), &db);
+            println!("working it out...");
+
+            let stats_request = stats::gather(&collection::view(&db), webcache, &settings::load());
+            webcache = stats_request.1;
+
+            clear(webcache.clone(), &db);
+            print!("{}", stats::render(&stats_request.0, text::Format::Text));
+
+            util::save_cache(webcache.clone(), CACHE_LOC);
+            pause();
+            return (db, webcache);
+        },
+        2 => text::Format::Markdown,
+        3 => text::Format::Text,
+        _ => {
+            pause_console!("Incorrect Option! Hit Enter to go back!");
+            return (db, webcache);
+        }
+    };
+
+    let filter_request = query::filter(&collection::view(&db), &clauses, webcache);
+    println!("generating report...");
+    let data_request = report::gather(&filter_request.0, filter_request.1, &settings::load());
+    webcache = data_request.1;
+
+    util::save_cache(webcache.clone(), CACHE_LOC);
+
+    let output = text::render(&data_request.0, format);
+    let path = if format == text::Format::Markdown { MARKDOWN_LOC } else { TEXT_LOC };
+
+    clear(webcache.clone(), &db);
+
+    println!("[1] save to {} [2] print it here", path);
+    let destination: i32 = read!();
+
+    if destination == 2
+    {
+        println!("{}", output);
+    }
+    else if util::write_file(path, &output)
+    {
+        println!("generated {}!", path);
+    }
+    else
+    {
+        println!("[debug] [error] could not write {}!", path);
+    }
+
+    pause();
+
+    (db, webcache)
+}
+
+fn diff_report(db: Database, mut webcache: HashMap<String, String>) -> (Database, HashMap<String, String>)
+{
+    let sources = snapshot::sources(DB_LOC);
+
+    clear(webcache.clone(), &db);
+
+    if sources.is_empty()
+    {
+        println!("nothing to compare against yet! take a price snapshot or save a change first");
+        pause();
+        return (db, webcache);
+    }
+
+    println!("compare from:");
+
+    for (i, source) in sources.iter().enumerate()
+    {
+        println!("[{}] {}", i, source.label);
+    }
+
+    let old_pos: usize = read!();
+
+    println!("compare to: (current for the collection right now)");
+
+    for (i, source) in sources.iter().enumerate()
+    {
+        println!("[{}] {}", i, source.label);
+    }
+
+    let new_choice: String = read!();
+
+    let settings = settings::load();
+
+    let old_request = pick_snapshot(&old_pos.to_string(), &sources, &db, webcache, &settings);
+    let new_request = pick_snapshot(&new_choice, &sources, &db, old_request.1, &settings);
+    webcache = new_request.1;
+
+    let ((old, old_label), (new, new_label)) = match (old_request.0, new_request.0)
+    {
+        (Some(old), Some(new)) => (old, new),
+        _ => {
+            pause_console!("Incorrect Option! Hit Enter to go back!");
+            return (db, webcache);
+        }
+    };
+
+    let diff_request = diff::compare(&old, &new, &old_label, &new_label, webcache, &settings);
+    webcache = diff_request.1;
+
+    util::save_cache(webcache.clone(), CACHE_LOC);
+    clear(webcache.clone(), &db);
+
+    println!("[1] web page ({}) [2] save to {} [3] print it here", html::DIFF_LOC, DIFF_TEXT_LOC);
+    let destination: i32 = read!();
+
+    let result = match destination
+    {
+        1 => html::generate_diff(&diff_request.0, &settings).map(|()| Some(html::DIFF_LOC)),
+        3 => {
+            println!("{}", diff::text(&diff_request.0, &load_currency(&settings)));
+            Ok(None)
+        },
+        _ => match util::write_file(DIFF_TEXT_LOC, &diff::text(&diff_request.0, &load_currency(&settings)))
+        {
+            true => Ok(Some(DIFF_TEXT_LOC)),
+            false => Err(format!("could not write {}!", DIFF_TEXT_LOC))
+        }
+    };
+
+    match result
+    {
+        Ok(Some(path)) => println!("generated {}!", path),
+        Ok(None) => (),
+        Err(error) => println!("[debug] [error] {}", error)
+    }
+
+    pause();
+
+    (db, webcache)
+}
+
+// asks which cards the report should cover, None after telling the user their query didn't make sense
+fn ask_query() -> Option<Vec<query::Clause>>
+{
+    print!("Only cards matching (eg. color=red cost>=5, blank for all): ");
+    let input: String = read!("{}\n");
+
+    match query::parse(&input)
+    {
+        Ok(clauses) => Some(clauses),
+        Err(error) => {
+            pause_console!("{}! Hit Enter to go back!", error);
+            None
+        }
+    }
+}
+
+// binder makes the print-ready sheets instead of the normal page, clauses narrow it down to the cards that match
+fn generate_html_report(db: Database, webcache: HashMap<String, String>, binder: bool, clauses: &[query::Clause]) -> (Database, HashMap<String, String>)
+{
+    let settings = settings::load();
+    let (view, webcache) = query::filter(&collection::view(&db), clauses, webcache);
+
+    println!("generating report...");
+
+    let (generate_result, report_path) = if binder
+    {
+        (html::generate_binder(view, webcache.clone(), &settings, html::binder_path(&settings)), html::binder_path(&settings))
+    }
+    else
+    {
+        (html::generate(view, webcache.clone(), &settings, html::report_path(&settings)), html::report_path(&settings))
+    };
+
+    let webcache = generate_result.1;
+
+    let result = generate_result.0;
+
+    clear(webcache.clone(), &db);
+
+    if let Err(error) = result
+    {
+        println!("[debug] [error] {}", error);
+    }
+    else
+    {
+        util::save_cache(webcache.clone(), CACHE_LOC);
+        println!("generated a report! open {}? (y/n)", report_path);
+
+        let confirmation: char = read!();
+
+        if confirmation == 'y'
+        {
+            let _ = if cfg!(target_os = "windows") {
+                Command::new("cmd")
+                    .args(["/C", "start", "", report_path])
+                    .spawn()
+                    .unwrap()
+            } else {
                 Command::new("xdg-open")
-                    .arg("report.html")
+                    .arg(report_path)
                     .spawn()
                     .unwrap()
             };
         }
     }
+
+    pause();
+
+    (db, webcache)
+}
+
+fn export_collection(db: Database, mut webcache: HashMap<String, String>) -> (Database, HashMap<String, String>)
+{
+    clear(webcache.clone(), &db);
+
+    println!("[1] spreadsheet (csv) [2] tcgplayer mass entry [3] have list (cards beyond a playset)");
+    let input: i32 = read!();
+
+    let settings = settings::load();
+
+    let (export_request, path) = match input
+    {
+        1 => (export::csv(&collection::view(&db), webcache.clone(), &settings, &load_currency(&settings)), CSV_LOC),
+        2 => (export::tcgplayer(&collection::view(&db), webcache.clone(), &settings), TCGPLAYER_LOC),
+        3 => (playset::have_list(&collection::view(&db), webcache.clone(), &settings), HAVE_LIST_LOC),
+        _ => {
+            pause_console!("Incorrect Option! Hit Enter to go back!");
+            return (db, webcache);
+        }
+    };
+
+    webcache = export_request.1;
+
+    clear(webcache.clone(), &db);
+
+    if util::write_file(path, &export_request.0)
+    {
+        util::save_cache(webcache.clone(), CACHE_LOC);
+        println!("exported collection to {}!", path);
+    }
+    else
+    {
+        println!("[debug] [error] could not write {}!", path);
+    }
+
+    pause();
+
+    (db, webcache)
+}
+
+fn collections(mut db: Database, webcache: HashMap<String, String>) -> Database
+{
+    clear(webcache.clone(), &db);
+
+    for (i, name) in db.collections.iter().enumerate()
+    {
+        let count = db.cards.iter().filter(|card| &card.collection == name).count();
+        println!("[{}] {} ({} cards)", i, name, count);
+    }
+
+    println!("\n[1] switch collection [2] show all collections [3] new collection [4] move a card [5] delete empty collection [6] back");
+    let input: i32 = read!();
+
+    let changed = match input
+    {
+        1 => {
+            print!("Collection: ");
+            let selection: usize = read!();
+
+            match db.collections.get(selection).cloned()
+            {
+                None => false,
+                Some(name) => collection::switch(&mut db, Some(&name))
+            }
+        },
+        2 => collection::switch(&mut db, None),
+        3 => {
+            print!("New Collection Name: ");
+            let name: String = read!("{}\n");
+
+            collection::create(&mut db, &name)
+        },
+        4 => {
+            print!("Card Pos: ");
+            let selection: usize = read!();
+            print!("To Collection: ");
+            let to: usize = read!();
+
+            match (collection::view_indices(&db).get(selection).cloned(), db.collections.get(to).cloned())
+            {
+                (Some(index), Some(name)) => collection::move_card(&mut db, index, &name),
+                _ => false
+            }
+        },
+        5 => {
+            print!("Collection: ");
+            let selection: usize = read!();
+
+            match db.collections.get(selection).cloned()
+            {
+                None => false,
+                Some(name) => collection::delete(&mut db, &name)
+            }
+        },
+        6 => return db,
+        _ => false
+    };
+
+    if !changed
+    {
+        pause_console!("Could not do that! Hit Enter to go back!");
+        return db;
+    }
+
+    let save_result = save_db(db.clone(), DB_LOC);
+
+    if !save_result
+    {
+        println!("[debug] [error] Database could not be saved!");
+        pause();
+    }
+
+    db
+}
+
+fn trades(mut db: Database, mut webcache: HashMap<String, String>) -> (Database, HashMap<String, String>)
+{
+    clear(webcache.clone(), &db);
+
+    for (i, name) in db.owners.iter().enumerate()
+    {
+        let count = trade::holdings(&db, name).len();
+        println!("[{}] {} ({} cards)", i, name, count);
+    }
+
+    println!("\n[1] new owner [2] set card owner [3] new trade [4] trade history [5] back");
+    let input: i32 = read!();
+
+    let changed = match input
+    {
+        1 => {
+            print!("Owner Name: ");
+            let name: String = read!("{}\n");
+
+            trade::create_owner(&mut db, &name)
+        },
+        2 => {
+            print!("Card Pos: ");
+            let selection: usize = read!();
+            print!("Owner: ");
+            let owner: usize = read!();
+
+            match (collection::view_indices(&db).get(selection).cloned(), db.owners.get(owner).cloned())
+            {
+                (Some(index), Some(name)) => trade::set_owner(&mut db, index, &name),
+                _ => false
+            }
+        },
+        3 => {
+            let trade_request = new_trade(db.clone(), webcache.clone());
+            webcache = trade_request.1;
+
+            match trade_request.0
+            {
+                None => return (db, webcache),
+                Some(traded) => {
+                    db = traded;
+                    true
+                }
+            }
+        },
+        4 => {
+            trade_history(&db);
+            return (db, webcache);
+        },
+        5 => return (db, webcache),
+        _ => false
+    };
+
+    if !changed
+    {
+        pause_console!("Could not do that! Hit Enter to go back!");
+        return (db, webcache);
+    }
+
+    let save_result = save_db(db.clone(), DB_LOC);
+
+    if !save_result
+    {
+        println!("[debug] [error] Database could not be saved!");
+        pause();
+    }
+
+    (db, webcache)
+}
+
+// returns the traded database once both sides confirm, None if the trade was called off
+fn new_trade(mut db: Database, mut webcache: HashMap<String, String>) -> (Option<Database>, HashMap<String, String>)
+{
+    let settings = settings::load();
+    let currency = load_currency(&settings);
+
+    print!("First Owner: ");
+    let first: usize = read!();
+    print!("Second Owner: ");
+    let second: usize = read!();
+
+    let (first_owner, second_owner) = match (db.owners.get(first).cloned(), db.owners.get(second).cloned())
+    {
+        (Some(first_owner), Some(second_owner)) if first_owner != second_owner => (first_owner, second_owner),
+        _ => {
+            pause_console!("Pick two different owners! Hit Enter to go back!");
+            return (None, webcache);
+        }
+    };
+
+    let mut picks: Vec<Vec<usize>> = Vec::new();
+
+    for owner in [&first_owner, &second_owner]
+    {
+        clear(webcache.clone(), &db);
+
+        let holdings = trade::holdings(&db, owner);
+
+        for (i, index) in holdings.iter().enumerate()
+        {
+            let product_request = util::try_get_product_details(&format_id(db.cards[*index].product_id.clone()), webcache.clone());
+            webcache = product_request.1;
+
+            let product_info: Value = serde_json::from_str(&or_report(product_request.0)).unwrap_or_default();
+            let card = trade::trade_card(&db.cards[*index], &product_info, &settings);
+            println!("[{}] {} {}", i, card.name, currency::format(&currency, card.value));
+        }
+
+        print!("\nCards {} gives (eg. 0,2,3): ", owner);
+        let selection: String = read!("{}\n");
+
+        let picked = match util::parse_indices(&selection)
+        {
+            None => None,
+            Some(positions) => positions.iter().map(|pos| holdings.get(*pos).cloned()).collect::<Option<Vec<usize>>>()
+        };
+
+        match picked
+        {
+            None => {
+                pause_console!("Incorrect Selection Format! Hit Enter to go back!");
+                return (None, webcache);
+            },
+            Some(indices) => picks.push(indices)
+        }
+    }
+
+    let proposal_request = trade::propose(&db, &first_owner, &second_owner, &picks[0], &picks[1], &settings, webcache.clone());
+    webcache = proposal_request.1;
+
+    let proposal = match proposal_request.0
+    {
+        None => {
+            pause_console!("Could not price that trade! Hit Enter to go back!");
+            return (None, webcache);
+        },
+        Some(proposal) => proposal
+    };
+
+    clear(webcache.clone(), &db);
+    print_trade(&proposal, &currency);
+
+    println!("\nConfirm trade? (y/n)");
+    let confirmation: char = read!();
+
+    if confirmation != 'y' || !trade::execute(&mut db, proposal, &picks[0], &picks[1])
+    {
+        return (None, webcache);
+    }
+
+    (Some(db), webcache)
+}
+
+// trades remember dollars, shown at today's exchange rate
+fn print_trade(trade: &trade::Trade, currency: &currency::Currency)
+{
+    let first_total = trade::total(&trade.first_gives);
+    let second_total = trade::total(&trade.second_gives);
+
+    for (owner, cards, total) in [(&trade.first_owner, &trade.first_gives, first_total), (&trade.second_owner, &trade.second_gives, second_total)]
+    {
+        println!("{} gives:", owner);
+
+        for card in cards
+        {
+            println!("  {} {}", card.name, currency::format(currency, card.value));
+        }
+
+        println!("  Total: {}\n", currency::format(currency, total));
+    }
+
+    let difference = first_total - second_total;
+
+    if difference > 0.0
+    {
+        println!("Difference: {} gives {} more", trade.first_owner, currency::format(currency, difference));
+    }
+    else if difference < 0.0
+    {
+        println!("Difference: {} gives {} more", trade.second_owner, currency::format(currency, -difference));
+    }
     else
     {
-        println!("[debug] [error] could not generate a report!");
+        println!("Difference: even trade");
+    }
+}
+
+fn trade_history(db: &Database)
+{
+    let currency = load_currency(&settings::load());
+
+    if db.trades.is_empty()
+    {
+        println!("No trades yet!");
+    }
+
+    for trade in &db.trades
+    {
+        println!("---- {} ----", trade.date.format("%Y-%m-%d %H:%M"));
+        print_trade(trade, &currency);
+        println!();
     }
 
     pause();
+}
+
+// blank or unreadable input means no amount was given
+fn read_amount(prompt: &str) -> Option<f64>
+{
+    print!("{}", prompt);
+    let input: String = read!("{}\n");
+
+    input.trim().trim_start_matches('$').parse::<f64>().ok()
+}
+
+// typed in the currency shown, kept in dollars like every price
+fn read_money(prompt: &str, currency: &currency::Currency) -> Option<f64>
+{
+    print!("{}{}", prompt, currency.symbol);
+    let input: String = read!("{}\n");
+
+    input.trim().trim_start_matches(currency.symbol.trim()).trim().parse::<f64>().ok().map(|amount| amount / currency.rate)
+}
+
+fn booster_packs(db: Database, webcache: HashMap<String, String>) -> (Database, HashMap<String, String>)
+{
+    clear(webcache.clone(), &db);
+
+    println!("[1] open packs [2] history");
+    let input: i32 = read!();
+
+    match input
+    {
+        1 => open_packs(db, webcache),
+        2 => {
+            clear(webcache.clone(), &db);
+
+            let summary_request = opening::summary(&db, webcache);
+            print!("{}", opening::text(&summary_request.0, &load_currency(&settings::load())));
+
+            pause();
+            (db, summary_request.1)
+        },
+        _ => {
+            pause_console!("Incorrect Option! Hit Enter to go back!");
+            (db, webcache)
+        }
+    }
+}
+
+// a session of pulls typed in by OP code, added all at once at the end
+fn open_packs(mut db: Database, mut webcache: HashMap<String, String>) -> (Database, HashMap<String, String>)
+{
+    let currency = load_currency(&settings::load());
+
+    print!("Set (eg. romance-dawn): ");
+    let set_name: String = read!("{}\n");
+    let set_name = set_name.trim().to_string();
+
+    let cost = match read_money("What did it cost? (eg. 89.99 for a box): ", &currency)
+    {
+        Some(cost) if cost >= 0.0 => cost,
+        _ => {
+            pause_console!("Incorrect Cost Format! Hit Enter to go back!");
+            return (db, webcache);
+        }
+    };
+
+    print!("How many packs? (blank for a box of {}): ", opening::BOX_PACKS);
+    let packs_string: String = read!("{}\n");
+
+    let packs = match packs_string.trim()
+    {
+        "" => opening::BOX_PACKS,
+        packs => match packs.parse::<u32>()
+        {
+            Ok(packs) => packs,
+            Err(_) => {
+                pause_console!("Incorrect Pack Count! Hit Enter to go back!");
+                return (db, webcache);
+            }
+        }
+    };
+
+    let mut pulls: Vec<opening::Pull> = Vec::new();
+    let mut last_code: Option<String> = None;
+
+    loop
+    {
+        let pulled_value = opening::value(&pulls);
+        println!("\n{} pulls worth {} so far, {} spent", pulls.len(), currency::format(&currency, pulled_value), currency::format(&currency, cost));
+
+        print!("Pull (OP code like OP01-024, just 024 for the same set, - to take back the last one, blank when done): ");
+        let typed: String = read!("{}\n");
+
+        match typed.trim()
+        {
+            "" => break,
+            "-" => {
+                if let Some(pull) = pulls.pop()
+                {
+                    println!("took back {}", pull.name);
+                }
+
+                continue;
+            },
+            _ => ()
+        }
+
+        let code = match opening::full_code(&typed, last_code.as_deref())
+        {
+            Some(code) => code,
+            None => {
+                println!("Type the whole OP code the first time, eg. OP01-024!");
+                continue;
+            }
+        };
+
+        let printings_request = opening::printings(&code, &set_name, webcache);
+        webcache = printings_request.1;
+
+        let mut printings = printings_request.0;
+
+        let pull = match printings.len()
+        {
+            0 => {
+                println!("No {} in {}!", code, set_name);
+                continue;
+            },
+            1 => printings.remove(0),
+            _ => {
+                for (i, printing) in printings.iter().enumerate()
+                {
+                    let price = printing.price.map(|price| currency::format(&currency, price)).unwrap_or("no price".to_string());
+                    println!("[{}] {} | {} | {}", i, printing.name, printing.rarity, price);
+                }
+
+                print!("Which one?: ");
+                let choice: String = read!("{}\n");
+
+                match choice.trim().parse::<usize>().ok().filter(|choice| *choice < printings.len())
+                {
+                    Some(choice) => printings.remove(choice),
+                    None => {
+                        println!("No card at that position!");
+                        continue;
+                    }
+                }
+            }
+        };
+
+        println!("+ {}", pull.name);
+
+        last_code = Some(code);
+        pulls.push(pull);
+    }
+
+    if pulls.is_empty()
+    {
+        pause_console!("Nothing pulled, nothing added! Hit Enter to go back!");
+        return (db, webcache);
+    }
+
+    let session = opening::Opening { date: chrono::Local::now(), set_name: pulls[0].set_name.clone(), packs, cost, pulls };
+    let pulled_value = opening::value(&session.pulls);
+
+    println!("\n{} cards worth {} for {} ({})", session.pulls.len(), currency::format(&currency, pulled_value), currency::format(&currency, cost), currency::signed(&currency, pulled_value - cost));
+    print!("Add them to the collection? (y/n): ");
+
+    let confirmation: String = read!("{}\n");
+
+    if confirmation.trim() != "y"
+    {
+        pause_console!("Nothing added! Hit Enter to go back!");
+        return (db, webcache);
+    }
+
+    opening::save(&mut db, session);
+
+    if !save_db(db.clone(), DB_LOC)
+    {
+        println!("[debug] [error] Database could not be saved!");
+        pause();
+    }
 
     (db, webcache)
-}
\ No newline at end of file
+}
+
+fn sales_ledger(db: &Database, webcache: HashMap<String, String>)
+{
+    clear(webcache, db);
+
+    let currency = load_currency(&settings::load());
+    let money = |value: f64| currency::format(&currency, value);
+
+    println!("| Date | Name | Kind | To | Sale Price | Fees | Shipping | Cost | Profit |");
+
+    for disposal in &db.archive
+    {
+        let cost = match disposal.purchase_cost
+        {
+            None => "?".to_string(),
+            Some(cost) => money(cost)
+        };
+
+        let profit = match ledger::profit(disposal)
+        {
+            None => "-".to_string(),
+            Some(profit) => money(profit)
+        };
+
+        println!("| {} | {} | {} | {} | {} | {} | {} | {} | {} |", disposal.date, disposal.name, ledger::kind_name(disposal.kind), trade::owner_name(&disposal.counterparty), money(disposal.sale_price), money(disposal.fees), money(disposal.shipping), cost, profit);
+    }
+
+    for (title, column, lines) in [("Per Card", "Card", ledger::by_card(db)), ("Per Month", "Month", ledger::by_period(db))]
+    {
+        println!("\n{}:", title);
+        println!("| {} | Count | Revenue | Realized Profit |", column);
+
+        for line in &lines
+        {
+            println!("| {} | {} | {} | {}{} |", line.label, line.count, money(line.revenue), money(line.profit), if line.missing_cost { " *" } else { "" });
+        }
+    }
+
+    println!("\nTotal Realized Profit: {}", money(ledger::total(&ledger::by_card(db))));
+
+    if currency.date.is_some()
+    {
+        println!("{}", currency::rate_note(&currency));
+    }
+
+    println!("* some of these had no purchase cost, so they count as free");
+
+    pause();
+}
+
+fn change_settings(db: &Database, webcache: HashMap<String, String>)
+{
+    clear(webcache, db);
+
+    let mut settings = settings::load();
+
+    println!("Settings are kept in {}\n", settings::config_dir().display());
+    println!("[1] report theme ({}) [2] report images ({}) [3] price basis ({}) [4] condition multipliers [5] currency ({}) [6] back", settings.theme, settings::report_mode_name(settings.report_mode), valuation::basis_name(settings.price_basis), settings.currency);
+    let input: i32 = read!();
+
+    if input == 5
+    {
+        let codes = currency::codes(&settings);
+
+        if codes.len() == 1
+        {
+            println!("[!] could not get exchange rates! put a rates.json in {} to use your own", settings::config_dir().display());
+        }
+
+        for (i, code) in codes.iter().enumerate()
+        {
+            println!("[{}] {}", i, code);
+        }
+
+        print!("Currency: ");
+        let selection: usize = read!();
+
+        match codes.get(selection)
+        {
+            None => { pause_console!("Incorrect Option! Hit Enter to go back!"); },
+            Some(code) => {
+                settings.currency = code.clone();
+
+                if !settings::save(&settings)
+                {
+                    println!("[debug] [error] Settings could not be saved!");
+                    pause();
+                }
+            }
+        };
+
+        return;
+    }
+
+    if input == 3
+    {
+        for (i, basis) in valuation::PRICE_BASES.iter().enumerate()
+        {
+            println!("[{}] {}", i, valuation::basis_name(*basis));
+        }
+
+        print!("Price Basis: ");
+        let selection: usize = read!();
+
+        match valuation::PRICE_BASES.get(selection)
+        {
+            None => { pause_console!("Incorrect Option! Hit Enter to go back!"); },
+            Some(basis) => {
+                settings.price_basis = *basis;
+
+                if !settings::save(&settings)
+                {
+                    println!("[debug] [error] Settings could not be saved!");
+                    pause();
+                }
+            }
+        };
+
+        return;
+    }
+
+    if input == 4
+    {
+        for (i, condition) in valuation::CONDITIONS.iter().enumerate()
+        {
+            println!("[{}] {} (x{})", i, valuation::condition_name(*condition), valuation::multiplier(*condition, &settings));
+        }
+
+        print!("Condition: ");
+        let selection: usize = read!();
+
+        let condition = match valuation::CONDITIONS.get(selection)
+        {
+            None => {
+                pause_console!("Incorrect Option! Hit Enter to go back!");
+                return;
+            },
+            Some(condition) => *condition
+        };
+
+        match read_amount("Multiplier (eg. 0.85): ")
+        {
+            Some(multiplier) if multiplier >= 0.0 => {
+                settings.condition_multipliers.insert(condition, multiplier);
+
+                if !settings::save(&settings)
+                {
+                    println!("[debug] [error] Settings could not be saved!");
+                    pause();
+                }
+            },
+            _ => { pause_console!("Incorrect Amount! Hit Enter to go back!"); }
+        };
+
+        return;
+    }
+
+    if input == 2
+    {
+        let modes = [ReportMode::Embedded, ReportMode::Folder, ReportMode::Cdn];
+
+        for (i, mode) in modes.iter().enumerate()
+        {
+            println!("[{}] {}", i, settings::report_mode_name(*mode));
+        }
+
+        print!("Report Images: ");
+        let selection: usize = read!();
+
+        match modes.get(selection)
+        {
+            None => { pause_console!("Incorrect Option! Hit Enter to go back!"); },
+            Some(mode) => {
+                settings.report_mode = *mode;
+
+                if !settings::save(&settings)
+                {
+                    println!("[debug] [error] Settings could not be saved!");
+                    pause();
+                }
+            }
+        };
+
+        return;
+    }
+
+    if input != 1
+    {
+        return;
+    }
+
+    let themes = html::themes();
+
+    for (i, theme) in themes.iter().enumerate()
+    {
+        println!("[{}] {}", i, theme);
+    }
+
+    print!("Theme: ");
+    let selection: usize = read!();
+
+    match themes.get(selection)
+    {
+        None => { pause_console!("Incorrect Option! Hit Enter to go back!"); },
+        Some(theme) => {
+            settings.theme = theme.clone();
+
+            if !settings::save(&settings)
+            {
+                println!("[debug] [error] Settings could not be saved!");
+                pause();
+            }
+        }
+    };
+}
@@ -0,0 +1,294 @@
+//! `mock`, a stand-in for tcgplayer that answers from recorded responses so everything can run without the network
+//!
+//! a fixtures folder holds one file per response:
+//! - `details/<product id>.json` for product details
+//! - `search/<query>.json` for searches, named by [`search_file`](crate::mock::search_file), with `-page1`, `-page2`... on the later pages,
+//!   always unfiltered, the mock applies the search's filters to them itself
+//! - `images/<product id>.jpg` for card pictures
+//!
+//! point [`util::API_URL_VAR`] and [`util::IMAGE_URL_VAR`] at it to use it
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::util;
+
+/// where the mock listens unless told otherwise
+pub const DEFAULT_PORT: u16 = 8090;
+/// the folder responses are read from and recorded to
+pub const DEFAULT_FIXTURES: &str = "fixtures";
+
+/// the file a page of a search is kept in without the .json, eg. "Luffy OP01" -> "luffy_op01" and on page 1 "luffy_op01-page1"
+pub fn search_file(query: &str, page: usize) -> String
+{
+    let name: String = query.trim().to_lowercase().chars()
+        .map(|character| if character.is_alphanumeric() { character } else { '_' })
+        .collect();
+
+    if page == 0
+    {
+        name
+    }
+    else
+    {
+        format!("{}-page{}", name, page)
+    }
+}
+
+/// replays the fixtures on a background thread without a word to the console, returns the url to point the api and image urls at
+///
+/// port 0 picks any free port, which is what tests want
+pub fn start(fixtures: &Path, port: u16) -> Result<String, String>
+{
+    let server = Server::http(("127.0.0.1", port)).map_err(|error| format!("could not start the mock server on port {}! {}", port, error))?;
+
+    let address = server.server_addr().to_ip().ok_or("the mock server has no ip address!")?;
+    let fixtures = fixtures.to_path_buf();
+
+    std::thread::spawn(move || {
+        for request in server.incoming_requests()
+        {
+            respond(request, &fixtures, false, false);
+        }
+    });
+
+    Ok(format!("http://{}", address))
+}
+
+/// `mock [port] [fixtures]` and `record [port] [fixtures]`, recording fetches anything missing from tcgplayer and saves it
+///
+/// runs in the foreground, printing each request it couldn't answer or recorded, and only comes back when it can't start
+pub fn serve(fixtures: &Path, port: u16, record: bool) -> Result<(), String>
+{
+    let server = Server::http(("127.0.0.1", port)).map_err(|error| format!("could not start the mock server on port {}! {}", port, error))?;
+
+    println!("[+] answering from {} on http://localhost:{}", fixtures.display(), port);
+
+    if record
+    {
+        println!("[+] anything missing is fetched from tcgplayer and saved");
+    }
+
+    println!("[-] run the program with {0}=http://localhost:{2} and {1}=http://localhost:{2} to use it", util::API_URL_VAR, util::IMAGE_URL_VAR, port);
+    println!("[-] press ctrl+c to stop");
+
+    for request in server.incoming_requests()
+    {
+        respond(request, fixtures, record, true);
+    }
+
+    Ok(())
+}
+
+// which fixture a request wants and where tcgplayer would have answered it
+struct Wanted
+{
+    path: PathBuf,
+    upstream: String,
+    content_type: &'static str,
+    /// the search body for searches, its filters are applied to the fixture
+    search: Option<Value>
+}
+
+fn wanted(request: &Request, body: &str, fixtures: &Path) -> Option<Wanted>
+{
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let query: HashMap<String, String> = url::form_urlencoded::parse(query.as_bytes()).into_owned().collect();
+
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let numeric = |id: &str| !id.is_empty() && id.chars().all(|character| character.is_ascii_digit());
+
+    match (request.method(), segments.as_slice())
+    {
+        (Method::Get, ["v2", "product", id, "details"]) if numeric(id) => Some(Wanted {
+            path: fixtures.join("details").join(format!("{}.json", id)),
+            upstream: format!("{}{}", util::DEFAULT_API_URL, url),
+            content_type: "application/json",
+            search: None
+        }),
+        (Method::Post, ["v1", "search", "request"]) => {
+            let search: Value = serde_json::from_str(body).unwrap_or_default();
+            let page = search["from"].as_u64().unwrap_or_default() as usize / util::SEARCH_PAGE_SIZE;
+
+            Some(Wanted {
+                path: fixtures.join("search").join(format!("{}.json", search_file(query.get("q")?, page))),
+                upstream: format!("{}{}", util::DEFAULT_API_URL, url),
+                content_type: "application/json",
+                search: Some(search)
+            })
+        },
+        (Method::Get, ["product", image]) => {
+            // full size for the images in the cache, 400 wide for cdn reports
+            let id = image.strip_suffix("_in_1000x1000.jpg").or_else(|| image.strip_suffix("_400w.jpg"))?;
+
+            numeric(id).then(|| Wanted {
+                path: fixtures.join("images").join(format!("{}.jpg", id)),
+                upstream: format!("{}{}", util::DEFAULT_IMAGE_URL, url),
+                content_type: "image/jpeg",
+                search: None
+            })
+        },
+        _ => None
+    }
+}
+
+// log says what's missing on the console, for the mock running in the foreground
+fn respond(mut request: Request, fixtures: &Path, record: bool, log: bool)
+{
+    let mut body = String::new();
+    let _ = request.as_reader().read_to_string(&mut body);
+
+    let wanted = match wanted(&request, &body, fixtures)
+    {
+        Some(wanted) => wanted,
+        None => {
+            let _ = request.respond(Response::from_string("nothing here").with_status_code(404));
+            return;
+        }
+    };
+
+    let mut data = std::fs::read(&wanted.path).ok();
+
+    if let (Some(fixture), Some(search)) = (&data, &wanted.search)
+    {
+        data = Some(filter_results(fixture, search));
+    }
+
+    if data.is_none() && record
+    {
+        data = match &wanted.search
+        {
+            // fixtures are unfiltered, so the search is recorded without its filters and filtered here like any other
+            Some(search) => fetch(&request, &wanted, &unfiltered(search).to_string()).map(|fixture| filter_results(&fixture, search)),
+            None => fetch(&request, &wanted, &body)
+        };
+    }
+
+    let response = match data
+    {
+        Some(data) => Response::from_data(data).with_header(Header::from_bytes("Content-Type", wanted.content_type).unwrap()),
+        None => {
+            if log
+            {
+                println!("[!] no fixture for {} {}, wanted {}", request.method(), request.url(), wanted.path.display());
+            }
+
+            Response::from_string("no fixture").with_status_code(404)
+        }
+    };
+
+    let _ = request.respond(response);
+}
+
+// one fixture answers a name whatever it's filtered by, so this does the filtering tcgplayer would have done
+fn filter_results(fixture: &[u8], search: &Value) -> Vec<u8>
+{
+    let mut response: Value = match serde_json::from_slice(fixture)
+    {
+        Ok(response) => response,
+        Err(_) => return fixture.to_vec()
+    };
+
+    let products = response["results"][0]["results"].as_array().cloned().unwrap_or_default();
+    let total = response["results"][0]["totalResults"].as_u64().unwrap_or(products.len() as u64);
+
+    let kept: Vec<Value> = products.iter().filter(|product| matches(product, &search["filters"])).cloned().collect();
+
+    if response["results"][0].is_object()
+    {
+        response["results"][0]["totalResults"] = (total - (products.len() - kept.len()) as u64).into();
+        response["results"][0]["results"] = kept.into();
+    }
+
+    response.to_string().into_bytes()
+}
+
+// the same search with only the product line filter left
+fn unfiltered(search: &Value) -> Value
+{
+    let mut search = search.clone();
+    search["filters"] = serde_json::json!({ "term": { "productLineName": [util::url_name(util::PRODUCT_LINE)] }, "range": {}, "match": {} });
+    search
+}
+
+// term filters match a product field or custom attribute, which tcgplayer compares by url name and so expects them sent as,
+// range filters a number on the product
+fn matches(product: &Value, filters: &Value) -> bool
+{
+    for (field, wanted) in filters["term"].as_object().cloned().unwrap_or_default()
+    {
+        let wanted: Vec<String> = wanted.as_array().cloned().unwrap_or_default().iter().filter_map(|value| value.as_str()).map(|value| value.to_string()).collect();
+
+        let value = if product[&field].is_null() { &product["customAttributes"][&field] } else { &product[&field] };
+
+        let have: Vec<String> = match value
+        {
+            Value::String(text) => vec![util::url_name(text)],
+            Value::Array(values) => values.iter().filter_map(|value| value.as_str()).map(util::url_name).collect(),
+            _ => Vec::new()
+        };
+
+        if !wanted.is_empty() && !have.iter().any(|name| wanted.contains(name))
+        {
+            return false;
+        }
+    }
+
+    for (field, bounds) in filters["range"].as_object().cloned().unwrap_or_default()
+    {
+        let number = match product[&field].as_f64()
+        {
+            Some(number) => number,
+            None => return false
+        };
+
+        if bounds["gte"].as_f64().is_some_and(|low| number < low) || bounds["lte"].as_f64().is_some_and(|high| number > high)
+        {
+            return false;
+        }
+    }
+
+    true
+}
+
+// asks tcgplayer the same question and saves the answer as a new fixture
+fn fetch(request: &Request, wanted: &Wanted, body: &str) -> Option<Vec<u8>>
+{
+    let client = reqwest::blocking::Client::builder().build().ok()?;
+
+    let upstream = if *request.method() == Method::Post
+    {
+        client.post(&wanted.upstream).header("Content-Type", "application/json").body(body.to_string())
+    }
+    else
+    {
+        client.get(&wanted.upstream)
+    };
+
+    let data = match upstream.send().and_then(|response| response.error_for_status()).and_then(|response| response.bytes())
+    {
+        Ok(data) => data.to_vec(),
+        Err(error) => {
+            println!("[debug] [error] could not record {}! {}", wanted.upstream, error);
+            return None;
+        }
+    };
+
+    let saved = wanted.path.parent().is_some_and(|folder| std::fs::create_dir_all(folder).is_ok()) && std::fs::write(&wanted.path, &data).is_ok();
+
+    if saved
+    {
+        println!("[+] recorded {}", wanted.path.display());
+    }
+    else
+    {
+        println!("[debug] [error] could not save {}!", wanted.path.display());
+    }
+
+    Some(data)
+}
@@ -0,0 +1,264 @@
+//! booster box and pack opening sessions: what went in, what came out, and whether it was worth it
+//!
+//! pulls are valued at market price when the session is saved, so later price changes don't rewrite history
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Local};
+use serde_json::{Number, Value};
+
+use crate::currency::{self, Currency};
+use crate::util::{self, format_id, Database};
+use crate::valuation::{self, Condition, PriceBasis};
+
+/// packs in a booster box
+pub const BOX_PACKS: u32 = 24;
+
+/// a card that came out of a pack
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Pull
+{
+    /// the printing's tcgplayer id
+    pub product_id: Number,
+    /// with the op code and pre release tag, what the menu shows
+    pub name: String,
+    /// the set it was printed in, eg. "Romance Dawn"
+    pub set_name: String,
+    /// eg. "Super Rare"
+    pub rarity: String,
+    /// market price when it was pulled, None when tcgplayer had none
+    pub price: Option<f64>
+}
+
+/// one sitting of opening packs from a single set
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Opening
+{
+    /// when the packs were opened
+    pub date: DateTime<Local>,
+    /// the set the packs came from
+    pub set_name: String,
+    /// how many packs were opened, 24 to a box
+    pub packs: u32,
+    /// what the packs cost in dollars
+    pub cost: f64,
+    /// every card worth keeping track of
+    pub pulls: Vec<Pull>
+}
+
+/// one session in the opening report
+#[derive(serde::Serialize, Clone)]
+pub struct SessionLine
+{
+    /// when the packs were opened
+    pub date: DateTime<Local>,
+    /// the set the packs came from
+    pub set_name: String,
+    /// how many packs were opened
+    pub packs: u32,
+    /// how many cards were kept track of
+    pub pulls: u32,
+    /// what the packs cost
+    pub cost: f64,
+    /// the pulls at their prices when opened
+    pub value_then: f64,
+    /// the same pulls at today's prices
+    pub value_now: f64
+}
+
+/// every session for one set added together
+#[derive(serde::Serialize, Clone)]
+pub struct SetLine
+{
+    /// the set
+    pub set_name: String,
+    /// how many sessions opened it
+    pub sessions: u32,
+    /// packs across those sessions
+    pub packs: u32,
+    /// what the packs cost
+    pub cost: f64,
+    /// the pulls at their prices when opened
+    pub value_then: f64,
+    /// the same pulls at today's prices
+    pub value_now: f64
+}
+
+/// every opening session, per session and per set
+#[derive(serde::Serialize, Clone)]
+pub struct Summary
+{
+    /// oldest first
+    pub sessions: Vec<SessionLine>,
+    /// by set name
+    pub sets: Vec<SetLine>,
+    /// what every pack cost
+    pub cost: f64,
+    /// every pull at its price when opened
+    pub value_then: f64,
+    /// every pull at today's prices
+    pub value_now: f64
+}
+
+/// what the pulls were worth when they were opened
+pub fn value(pulls: &[Pull]) -> f64
+{
+    pulls.iter().filter_map(|pull| pull.price).sum()
+}
+
+/// an OP code as typed, with a bare number like "024" borrowing the set part of the code before it, eg. "OP01-"
+///
+/// None when there's no earlier code to borrow from
+pub fn full_code(typed: &str, last_code: Option<&str>) -> Option<String>
+{
+    let typed = typed.trim().to_uppercase();
+
+    if !typed.chars().all(|character| character.is_ascii_digit())
+    {
+        return Some(typed);
+    }
+
+    let prefix = last_code?.rsplit_once('-')?.0;
+
+    Some(format!("{}-{}", prefix, typed))
+}
+
+/// every printing of a card number in a set, eg. the normal and alt art OP01-024 in Romance Dawn
+pub fn printings(code: &str, set_name: &str, webcache: HashMap<String, String>) -> (Vec<Pull>, HashMap<String, String>)
+{
+    let filters = util::SearchFilters { set: Some(set_name.to_string()), ..Default::default() };
+
+    let search_request = util::search_page(code, 0, &filters, webcache);
+    let response: Value = serde_json::from_str(&search_request.0).unwrap_or_default();
+
+    let products = response["results"][0]["results"].as_array().cloned().unwrap_or_default();
+
+    // tcgplayer's search is loose, so anything with another number is dropped
+    let pulls: Vec<Pull> = products.iter()
+        .filter(|product_info| product_info["productLineName"].as_str() == Some(util::PRODUCT_LINE))
+        .filter(|product_info| product_info["customAttributes"]["number"].as_str().is_some_and(|number| number.eq_ignore_ascii_case(code)))
+        .filter_map(|product_info| {
+            Some(Pull {
+                product_id: product_info["productId"].as_number()?.clone(),
+                name: util::display_name(product_info),
+                set_name: util::set_name(product_info),
+                rarity: product_info["rarityName"].as_str().unwrap_or_default().to_string(),
+                price: valuation::unit_price(product_info, PriceBasis::Market)
+            })
+        })
+        .collect();
+
+    (pulls, search_request.1)
+}
+
+/// cards in the opening go into the collection being looked at, and the session is kept for the history
+pub fn save(db: &mut Database, opening: Opening)
+{
+    let collection = crate::collection::target(db);
+
+    for pull in &opening.pulls
+    {
+        db.cards.push(util::Card { product_id: pull.product_id.clone(), collection: collection.clone(), owner: String::new(), condition: Condition::NearMint });
+    }
+
+    db.openings.push(opening);
+}
+
+/// every session and every set, what went in against what came out then and now
+pub fn summary(db: &Database, mut webcache: HashMap<String, String>) -> (Summary, HashMap<String, String>)
+{
+    let mut sessions: Vec<SessionLine> = Vec::new();
+
+    for opening in &db.openings
+    {
+        let mut value_now = 0.0;
+
+        for pull in &opening.pulls
+        {
+            let price_request = valuation::price(&format_id(pull.product_id.clone()), PriceBasis::Market, webcache);
+            webcache = price_request.1;
+
+            value_now += price_request.0.unwrap_or(0.0);
+        }
+
+        sessions.push(SessionLine {
+            date: opening.date,
+            set_name: opening.set_name.clone(),
+            packs: opening.packs,
+            pulls: opening.pulls.len() as u32,
+            cost: opening.cost,
+            value_then: value(&opening.pulls),
+            value_now
+        });
+    }
+
+    sessions.sort_by_key(|session| session.date);
+
+    let mut sets: BTreeMap<String, SetLine> = BTreeMap::new();
+
+    for session in &sessions
+    {
+        let set = sets.entry(session.set_name.clone()).or_insert(SetLine { set_name: session.set_name.clone(), sessions: 0, packs: 0, cost: 0.0, value_then: 0.0, value_now: 0.0 });
+
+        set.sessions += 1;
+        set.packs += session.packs;
+        set.cost += session.cost;
+        set.value_then += session.value_then;
+        set.value_now += session.value_now;
+    }
+
+    let summary = Summary {
+        cost: sessions.iter().map(|session| session.cost).sum(),
+        value_then: sessions.iter().map(|session| session.value_then).sum(),
+        value_now: sessions.iter().map(|session| session.value_now).sum(),
+        sessions,
+        sets: sets.into_values().collect()
+    };
+
+    (summary, webcache)
+}
+
+// eg. "+$12.40 (114%)", what came out against what went in
+fn return_text(value: f64, cost: f64, currency: &Currency) -> String
+{
+    if cost > 0.0
+    {
+        format!("{} ({:.0}%)", currency::signed(currency, value - cost), value / cost * 100.0)
+    }
+    else
+    {
+        currency::signed(currency, value - cost)
+    }
+}
+
+/// the history as text for the console, each session then each set
+pub fn text(summary: &Summary, currency: &Currency) -> String
+{
+    let money = |value: f64| currency::format(currency, value);
+
+    let mut output = String::new();
+
+    output.push_str("| Date | Set | Packs | Pulls | Cost | Pulled Value | Value Now | Return |\n");
+
+    for session in &summary.sessions
+    {
+        output.push_str(&format!("| {} | {} | {} | {} | {} | {} | {} | {} |\n", session.date.format("%Y-%m-%d"), session.set_name, session.packs, session.pulls, money(session.cost), money(session.value_then), money(session.value_now), return_text(session.value_then, session.cost, currency)));
+    }
+
+    output.push_str("\nPer Set:\n");
+    output.push_str("| Set | Sessions | Packs | Cost | Pulled Value | Value Now | Return |\n");
+
+    for set in &summary.sets
+    {
+        output.push_str(&format!("| {} | {} | {} | {} | {} | {} | {} |\n", set.set_name, set.sessions, set.packs, money(set.cost), money(set.value_then), money(set.value_now), return_text(set.value_then, set.cost, currency)));
+    }
+
+    output.push_str(&format!("\nTotal: {} spent, {} pulled ({} now), {}\n", money(summary.cost), money(summary.value_then), money(summary.value_now), return_text(summary.value_then, summary.cost, currency)));
+
+    if currency.date.is_some()
+    {
+        output.push_str(&format!("{}\n", currency::rate_note(currency)));
+    }
+
+    output
+}
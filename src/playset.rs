@@ -0,0 +1,287 @@
+//! how many copies of each card number we hold against what a deck can use, and what's left over to trade
+//!
+//! printings of the same card (reprints, alt arts, pre release stamps) share an OP code and count towards one playset
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::Local;
+
+use crate::currency::{self, Currency};
+use crate::gamedata;
+use crate::settings::Settings;
+use crate::util::Database;
+use crate::valuation::{self, Condition, PriceBasis};
+
+/// copies of one card number a deck can run
+pub const PLAYSET: u32 = 4;
+/// a deck has a single leader, so one is a full set
+pub const LEADER_PLAYSET: u32 = 1;
+
+/// a copy held beyond what a deck can run
+#[derive(serde::Serialize, Clone)]
+pub struct OwnedCopy
+{
+    /// the printing's tcgplayer id, formatted
+    pub product_id: String,
+    /// with the op code and pre release tag, what the menu shows
+    pub name: String,
+    /// the set it was printed in
+    pub set_name: String,
+    /// eg. "Super Rare"
+    pub rarity: String,
+    /// what shape the copy is in
+    pub condition: Condition,
+    /// one near mint copy, None when tcgplayer has no price
+    pub unit_price: Option<f64>,
+    /// after the condition multiplier, 0 when the price is unknown
+    pub value: f64
+}
+
+/// a card number held more times than a deck can run
+#[derive(serde::Serialize, Clone)]
+pub struct Surplus
+{
+    /// eg. "OP01-024"
+    pub number: String,
+    /// copies held across every printing
+    pub owned: u32,
+    /// how many a deck can run, 1 for leaders
+    pub playset: u32,
+    /// the copies beyond the playset, most valuable first
+    pub copies: Vec<OwnedCopy>,
+    /// what the extra copies are worth together
+    pub value: f64
+}
+
+/// a card number held fewer times than a deck can run
+#[derive(serde::Serialize, Clone)]
+pub struct Short
+{
+    /// eg. "OP01-024"
+    pub number: String,
+    /// the name of the cheapest printing held
+    pub name: String,
+    /// copies held across every printing
+    pub owned: u32,
+    /// copies still needed for a playset
+    pub missing: u32,
+    /// the missing copies at the cheapest held printing's price, None when none of them has a price
+    pub cost: Option<f64>
+}
+
+/// what's over and under a playset across the collection
+#[derive(serde::Serialize, Clone)]
+pub struct Analysis
+{
+    /// most valuable surplus first
+    pub surplus: Vec<Surplus>,
+    /// by card number
+    pub short: Vec<Short>,
+    /// copies held beyond a playset
+    pub surplus_count: u32,
+    /// what those copies are worth
+    pub surplus_value: f64
+}
+
+fn playset_size(card_type: &str) -> u32
+{
+    if card_type.eq_ignore_ascii_case("leader") { LEADER_PLAYSET } else { PLAYSET }
+}
+
+/// surplus and missing copies for every card number in the database, cards without an OP code (sleeves, DON!!) are left out
+///
+/// every printing plays the same, so the cheapest copies make up the playset and the rest are offered for trade,
+/// always at market price since that's what traders go by, whichever basis the reports use
+pub fn analyse(db: &Database, webcache: HashMap<String, String>, settings: &Settings) -> (Analysis, HashMap<String, String>)
+{
+    let market = Settings { price_basis: PriceBasis::Market, ..settings.clone() };
+
+    let valuation_request = valuation::value(db, webcache, &market);
+    let mut webcache = valuation_request.1;
+
+    // copies and the playset size by card number
+    let mut numbers: BTreeMap<String, (Vec<OwnedCopy>, u32)> = BTreeMap::new();
+
+    for card_value in valuation_request.0.cards
+    {
+        let lookup_request = gamedata::lookup(&card_value.product_id, webcache);
+        webcache = lookup_request.1;
+
+        let card = lookup_request.0;
+
+        if card.number.is_empty()
+        {
+            continue;
+        }
+
+        let entry = numbers.entry(card.number.to_uppercase()).or_insert((Vec::new(), playset_size(&card.card_type)));
+
+        entry.0.push(OwnedCopy {
+            product_id: card_value.product_id,
+            name: card_value.name,
+            set_name: card_value.set_name,
+            rarity: card.rarity,
+            condition: card_value.condition,
+            unit_price: card_value.unit_price,
+            value: card_value.value
+        });
+    }
+
+    let mut surplus: Vec<Surplus> = Vec::new();
+    let mut short: Vec<Short> = Vec::new();
+
+    for (number, (mut copies, playset)) in numbers
+    {
+        let owned = copies.len() as u32;
+
+        if owned < playset
+        {
+            let cheapest = copies.iter().min_by(|a, b| a.value.total_cmp(&b.value));
+            let unit_price = copies.iter().filter_map(|copy| copy.unit_price).min_by(f64::total_cmp);
+
+            short.push(Short {
+                number,
+                name: cheapest.map(|copy| copy.name.clone()).unwrap_or_default(),
+                owned,
+                missing: playset - owned,
+                cost: unit_price.map(|price| price * (playset - owned) as f64)
+            });
+
+            continue;
+        }
+
+        if owned == playset
+        {
+            continue;
+        }
+
+        // cheapest first, so everything after the playset is the best trade bait
+        copies.sort_by(|a, b| a.value.total_cmp(&b.value).then(a.name.cmp(&b.name)));
+
+        let mut extra: Vec<OwnedCopy> = copies.split_off(playset as usize);
+        extra.reverse();
+
+        surplus.push(Surplus { number, owned, playset, value: extra.iter().map(|copy| copy.value).sum(), copies: extra });
+    }
+
+    surplus.sort_by(|a, b| b.value.total_cmp(&a.value).then(a.number.cmp(&b.number)));
+
+    let analysis = Analysis {
+        surplus_count: surplus.iter().map(|found| found.copies.len() as u32).sum(),
+        surplus_value: surplus.iter().map(|found| found.value).sum(),
+        surplus,
+        short
+    };
+
+    (analysis, webcache)
+}
+
+/// the surplus and the missing copies as text for the console
+pub fn text(analysis: &Analysis, currency: &Currency) -> String
+{
+    let money = |value: f64| currency::format(currency, value);
+
+    let mut output = String::new();
+
+    output.push_str(&format!("Surplus: {} cards beyond a playset, worth {}\n", analysis.surplus_count, money(analysis.surplus_value)));
+
+    if analysis.surplus.is_empty()
+    {
+        output.push_str("  nothing\n");
+    }
+
+    for found in &analysis.surplus
+    {
+        output.push_str(&format!("  {} ({} owned, playset of {}): {}\n", found.number, found.owned, found.playset, money(found.value)));
+
+        for (copy, quantity) in grouped(&found.copies)
+        {
+            output.push_str(&format!("    {}x {}\n", quantity, describe(copy, currency)));
+        }
+    }
+
+    output.push_str(&format!("\nShort of a playset: {} cards\n", analysis.short.len()));
+
+    if analysis.short.is_empty()
+    {
+        output.push_str("  nothing\n");
+    }
+
+    for found in &analysis.short
+    {
+        let cost = found.cost.map(|cost| format!(", about {} to finish", money(cost))).unwrap_or_default();
+        output.push_str(&format!("  {}: {} owned, need {} more{}\n", found.name, found.owned, found.missing, cost));
+    }
+
+    output
+}
+
+/// a have list to paste into a trading group, every surplus copy with its condition and value
+pub fn have_list(db: &Database, webcache: HashMap<String, String>, settings: &Settings) -> (String, HashMap<String, String>)
+{
+    let analysis_request = analyse(db, webcache, settings);
+    let analysis = analysis_request.0;
+    let currency = currency::load(settings);
+
+    let mut output = format!("One Piece TCG have list, {}\n\n", Local::now().format("%Y-%m-%d"));
+
+    if analysis.surplus.is_empty()
+    {
+        output.push_str("Nothing spare right now!\n");
+    }
+
+    for found in &analysis.surplus
+    {
+        for (copy, quantity) in grouped(&found.copies)
+        {
+            output.push_str(&format!("{}x {}\n", quantity, describe(copy, &currency)));
+        }
+    }
+
+    output.push_str(&format!("\n{} cards, {} at {}\n", analysis.surplus_count, currency::format(&currency, analysis.surplus_value), valuation::basis_name(PriceBasis::Market)));
+
+    if currency.date.is_some()
+    {
+        output.push_str(&format!("{}\n", currency::rate_note(&currency)));
+    }
+
+    (output, analysis_request.1)
+}
+
+// identical copies (same product and condition) share a line, keeping their order
+fn grouped(copies: &[OwnedCopy]) -> Vec<(&OwnedCopy, u32)>
+{
+    let mut lines: Vec<(&OwnedCopy, u32)> = Vec::new();
+
+    for copy in copies
+    {
+        match lines.iter_mut().find(|(other, _)| other.product_id == copy.product_id && other.condition == copy.condition)
+        {
+            Some(line) => line.1 += 1,
+            None => lines.push((copy, 1))
+        }
+    }
+
+    lines
+}
+
+// eg. "Monkey.D.Luffy OP01-024 (Romance Dawn, Super Rare, Lightly Played) $10.63 each"
+fn describe(copy: &OwnedCopy, currency: &Currency) -> String
+{
+    let mut details = vec![copy.set_name.clone(), copy.rarity.clone()];
+
+    if copy.condition != Condition::NearMint
+    {
+        details.push(valuation::condition_name(copy.condition).to_string());
+    }
+
+    details.retain(|detail| !detail.is_empty());
+
+    let price = match copy.unit_price
+    {
+        Some(_) => format!("{} each", currency::format(currency, copy.value)),
+        None => "no price".to_string()
+    };
+
+    format!("{} ({}) {}", copy.name, details.join(", "), price)
+}
@@ -0,0 +1,239 @@
+//! collection queries on card game data, eg. `color=red subtype=straw hat crew counter=2000` or `cost=5 type=character`
+//!
+//! every clause has to match. numbers compare with = != < <= > >=, text with = (ignoring case and punctuation),
+//! != and ~ (contains). a word without an operator carries on the value before it, so names don't need quotes
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use crate::gamedata::{self, CardData};
+use crate::util::{self, format_id, Database};
+
+/// how a clause compares a field to its value
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator
+{
+    /// =
+    Equal,
+    /// !=
+    NotEqual,
+    /// ~, text only
+    Contains,
+    /// <, numbers only
+    Less,
+    /// <=, numbers only
+    LessOrEqual,
+    /// >, numbers only
+    Greater,
+    /// >=, numbers only
+    GreaterOrEqual
+}
+
+/// one condition of a query, eg. "cost>=5"
+#[derive(Clone, Debug)]
+pub struct Clause
+{
+    /// one of FIELDS
+    pub field: String,
+    /// how the field is compared
+    pub operator: Operator,
+    /// what it's compared to, as typed
+    pub value: String
+}
+
+/// everything a query can look at
+pub const FIELDS: [&str; 13] = ["name", "number", "set", "rarity", "type", "color", "attribute", "subtype", "cost", "power", "counter", "life", "effect"];
+
+const NUMBER_FIELDS: [&str; 4] = ["cost", "power", "counter", "life"];
+
+// two character operators first, so >= isn't read as >
+/// a word with any of these in it is a clause, eg. "cost>=5"
+pub const OPERATOR_CHARACTERS: [char; 5] = ['=', '!', '<', '>', '~'];
+
+const OPERATORS: [(&str, Operator); 7] = [
+    (">=", Operator::GreaterOrEqual),
+    ("<=", Operator::LessOrEqual),
+    ("!=", Operator::NotEqual),
+    ("=", Operator::Equal),
+    (">", Operator::Greater),
+    ("<", Operator::Less),
+    ("~", Operator::Contains)
+];
+
+fn operator_text(operator: Operator) -> &'static str
+{
+    OPERATORS.iter().find(|(_, known)| *known == operator).map(|(text, _)| *text).unwrap_or_default()
+}
+
+// "cost>=5" -> cost, >=, 5, None when the word doesn't start with a field and an operator
+// None for a word without an operator, which carries on the value before it, eg. "hat" in "subtype=straw hat"
+fn clause(word: &str) -> Result<Option<Clause>, String>
+{
+    let start = match word.find(OPERATOR_CHARACTERS)
+    {
+        None => return Ok(None),
+        Some(start) => start
+    };
+
+    let field = word[..start].to_lowercase();
+
+    if field.is_empty()
+    {
+        return Err(format!("{} needs a field in front of it, eg. cost{}", word, word));
+    }
+
+    // a misspelled field would otherwise end up in the value before it
+    if !FIELDS.contains(&field.as_str())
+    {
+        return Err(format!("{} isn't a field, try one of: {}", &word[..start], FIELDS.join(", ")));
+    }
+
+    match OPERATORS.iter().find(|(text, _)| word[start..].starts_with(text))
+    {
+        Some((text, operator)) => Ok(Some(Clause { field, operator: *operator, value: word[start + text.len()..].to_string() })),
+        None => Err(format!("{} needs an operator, eg. {}=", word, field))
+    }
+}
+
+/// reads a query, the error says what's wrong with it
+pub fn parse(text: &str) -> Result<Vec<Clause>, String>
+{
+    let mut clauses: Vec<Clause> = Vec::new();
+
+    for word in text.split_whitespace()
+    {
+        match (clause(word)?, clauses.last_mut())
+        {
+            (Some(clause), _) => clauses.push(clause),
+            (None, Some(last)) => {
+                last.value.push(' ');
+                last.value.push_str(word);
+            },
+            (None, None) => return Err(format!("{} isn't a query, try something like color=red cost>=5 (fields: {})", word, FIELDS.join(", ")))
+        }
+    }
+
+    for clause in &clauses
+    {
+        let numeric = NUMBER_FIELDS.contains(&clause.field.as_str());
+
+        if clause.value.trim().is_empty()
+        {
+            return Err(format!("{}{} needs a value", clause.field, operator_text(clause.operator)));
+        }
+
+        if numeric && (clause.operator == Operator::Contains || clause.value.trim().parse::<u32>().is_err())
+        {
+            return Err(format!("{} is compared with a number, eg. {}>=5", clause.field, clause.field));
+        }
+
+        if !numeric && !matches!(clause.operator, Operator::Equal | Operator::NotEqual | Operator::Contains)
+        {
+            return Err(format!("{} is text, compare it with =, != or ~", clause.field));
+        }
+    }
+
+    Ok(clauses)
+}
+
+/// the query back as text, eg. "color=red cost>=5"
+pub fn describe(clauses: &[Clause]) -> String
+{
+    clauses.iter().map(|clause| format!("{}{}{}", clause.field, operator_text(clause.operator), clause.value.trim())).collect::<Vec<String>>().join(" ")
+}
+
+fn text_values(card: &CardData, field: &str) -> Vec<String>
+{
+    match field
+    {
+        "name" => vec![card.name.clone()],
+        "number" => vec![card.number.clone()],
+        "set" => vec![card.set_name.clone()],
+        "rarity" => vec![card.rarity.clone()],
+        "type" => vec![card.card_type.clone()],
+        "color" => card.colors.clone(),
+        "attribute" => card.attributes.clone(),
+        "subtype" => card.subtypes.clone(),
+        "effect" => vec![card.effect.clone()],
+        _ => Vec::new()
+    }
+}
+
+fn number_value(card: &CardData, field: &str) -> Option<u32>
+{
+    match field
+    {
+        "cost" => card.cost,
+        "power" => card.power,
+        "counter" => card.counter,
+        "life" => card.life,
+        _ => None
+    }
+}
+
+fn clause_matches(card: &CardData, clause: &Clause) -> bool
+{
+    let value = clause.value.trim();
+
+    if NUMBER_FIELDS.contains(&clause.field.as_str())
+    {
+        let wanted: u32 = value.parse().unwrap_or_default();
+
+        // a card without the number only matches "isn't"
+        return match number_value(card, &clause.field)
+        {
+            None => clause.operator == Operator::NotEqual,
+            Some(number) => match clause.operator
+            {
+                Operator::Equal => number == wanted,
+                Operator::NotEqual => number != wanted,
+                Operator::Less => number < wanted,
+                Operator::LessOrEqual => number <= wanted,
+                Operator::Greater => number > wanted,
+                Operator::GreaterOrEqual => number >= wanted,
+                Operator::Contains => false
+            }
+        };
+    }
+
+    let values = text_values(card, &clause.field);
+    let equal = values.iter().any(|text| util::url_name(text) == util::url_name(value));
+
+    match clause.operator
+    {
+        Operator::Equal => equal,
+        Operator::NotEqual => !equal,
+        Operator::Contains => values.iter().any(|text| text.to_lowercase().contains(&value.to_lowercase())),
+        _ => false
+    }
+}
+
+/// true when the card fits every clause
+pub fn matches(card: &CardData, clauses: &[Clause]) -> bool
+{
+    clauses.iter().all(|clause| clause_matches(card, clause))
+}
+
+/// the database with only the cards that fit, for handing to reports the same way collection::view is
+pub fn filter(db: &Database, clauses: &[Clause], mut webcache: HashMap<String, String>) -> (Database, HashMap<String, String>)
+{
+    let mut fits: HashMap<String, bool> = HashMap::new();
+
+    for card in &db.cards
+    {
+        let product_id = format_id(card.product_id.clone());
+
+        if let Entry::Vacant(unchecked) = fits.entry(product_id)
+        {
+            let lookup_request = gamedata::lookup(unchecked.key(), webcache);
+            webcache = lookup_request.1;
+
+            unchecked.insert(matches(&lookup_request.0, clauses));
+        }
+    }
+
+    let mut filtered = db.clone();
+    filtered.cards.retain(|card| fits[&format_id(card.product_id.clone())]);
+
+    (filtered, webcache)
+}
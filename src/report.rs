@@ -0,0 +1,241 @@
+//! everything a report shows, gathered once so each output format lays out the same numbers
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::Value;
+
+use crate::currency::{self, Currency};
+use crate::settings::Settings;
+use crate::util::{self, format_id, Database};
+use crate::valuation::{self, Valuation};
+
+/// one card as the report shows it
+#[derive(serde::Serialize, Clone)]
+pub struct ReportCard
+{
+    /// the printing's tcgplayer id, formatted
+    pub product_id: String,
+    /// with the op code and pre release tag, what the menu shows
+    pub name: String,
+    /// the product's own name on tcgplayer
+    pub product_name: String,
+    /// eg. "OP01-024"
+    pub number: String,
+    /// the card text, empty when tcgplayer has none
+    pub description: String,
+    /// the set it was printed in
+    pub set_name: String,
+    /// eg. "Super Rare"
+    pub rarity: String,
+    /// eg. "Red" or "Red/Green" for multicolor cards
+    pub color: String,
+    /// eg. "Leader" or "Character"
+    pub card_type: String,
+    /// eg. "near mint"
+    pub condition: String,
+    /// after the condition multiplier, 0 when the price is unknown
+    pub market_value: f64,
+    /// false when tcgplayer has no price for the card
+    pub price_known: bool,
+    /// the card's tcgplayer page
+    pub url: String
+}
+
+/// the cards sharing a rarity, color or card type
+#[derive(serde::Serialize, Clone)]
+pub struct Group
+{
+    /// the rarity, color or card type
+    pub name: String,
+    /// how many cards have it
+    pub count: u32,
+    /// what they're worth together
+    pub value: f64
+}
+
+/// one set's cards in the report
+#[derive(serde::Serialize, Clone)]
+pub struct ReportSet
+{
+    /// the set's name
+    pub name: String,
+    /// how many cards are in it
+    pub count: u32,
+    /// what they're worth together
+    pub subtotal: f64,
+    /// every card from the set
+    pub cards: Vec<ReportCard>
+}
+
+/// everything a report shows, in dollars
+#[derive(serde::Serialize, Clone)]
+pub struct ReportData
+{
+    /// what every card is worth together
+    pub total_worth: f64,
+    /// how many cards there are
+    pub card_count: u32,
+    /// what the values are based on, eg. "market price"
+    pub price_basis: String,
+    /// cards counted as nothing because they have no price
+    pub unknown_prices: u32,
+    /// every value above is in dollars, this is what they're shown in
+    pub currency: Currency,
+    /// cards by set, by set name
+    pub sets: Vec<ReportSet>,
+    /// value by rarity
+    pub rarities: Vec<Group>,
+    /// value by color
+    pub colors: Vec<Group>,
+    /// value by card type
+    pub card_types: Vec<Group>,
+    /// the most valuable products, each product only once
+    pub top: Vec<ReportCard>
+}
+
+const TOP_COUNT: usize = 10;
+
+/// values and describes every card in db, looking up anything the web cache doesn't have yet
+pub fn gather(db: &Database, webcache: HashMap<String, String>, settings: &Settings) -> (ReportData, HashMap<String, String>)
+{
+    let valuation_request = valuation::value(db, webcache, settings);
+
+    let valuation: Valuation = valuation_request.0;
+    let mut webcache = valuation_request.1;
+
+    let mut cards: Vec<ReportCard> = Vec::new();
+
+    for (i, product) in db.cards.iter().enumerate()
+    {
+        let product_id = format_id(product.product_id.clone());
+        let product_info_request = util::get_product_details(&product_id, webcache.clone());
+
+        webcache = product_info_request.1;
+
+        let product_info: Value = serde_json::from_str(product_info_request.0.as_str()).unwrap_or_default();
+
+        let product_description: String = match product_info["customAttributes"]["description"].as_str()
+        {
+            None => "No description provided.".to_string(),
+            Some(description) => plain_text(description)
+        };
+
+        let card_value = &valuation.cards[i];
+
+        cards.push(ReportCard {
+            url: format!("https://www.tcgplayer.com/product/{}", product_id),
+            product_id,
+            name: util::display_name(&product_info),
+            product_name: product_info["productName"].as_str().unwrap_or_default().to_string(),
+            number: product_info["customAttributes"]["number"].as_str().unwrap_or_default().to_string(),
+            description: product_description,
+            set_name: card_value.set_name.clone(),
+            rarity: or_unknown(product_info["rarityName"].as_str().unwrap_or_default().to_string()),
+            color: or_unknown(util::attribute_text(&product_info["customAttributes"]["color"])),
+            card_type: or_unknown(util::attribute_text(&product_info["customAttributes"]["cardType"])),
+            condition: valuation::condition_name(card_value.condition).to_string(),
+            market_value: card_value.value,
+            price_known: card_value.unit_price.is_some()
+        });
+    }
+
+    let mut sets: BTreeMap<String, ReportSet> = BTreeMap::new();
+
+    for card in &cards
+    {
+        let set = sets.entry(card.set_name.clone()).or_insert(ReportSet { name: card.set_name.clone(), count: 0, subtotal: 0.0, cards: Vec::new() });
+
+        set.count += 1;
+        set.subtotal += card.market_value;
+        set.cards.push(card.clone());
+    }
+
+    let mut top: Vec<ReportCard> = Vec::new();
+
+    for card in &cards
+    {
+        if !top.iter().any(|other| other.product_id == card.product_id)
+        {
+            top.push(card.clone());
+        }
+    }
+
+    top.sort_by(|a, b| b.market_value.total_cmp(&a.market_value).then(a.name.cmp(&b.name)));
+    top.truncate(TOP_COUNT);
+
+    let data = ReportData {
+        total_worth: valuation.total,
+        card_count: valuation.card_count,
+        price_basis: valuation::basis_name(valuation.basis).to_string(),
+        unknown_prices: valuation.unknown,
+        currency: currency::load(settings),
+        sets: sets.into_values().collect(),
+        rarities: group(&cards, |card| &card.rarity),
+        colors: group(&cards, |card| &card.color),
+        card_types: group(&cards, |card| &card.card_type),
+        top
+    };
+
+    (data, webcache)
+}
+
+// most valuable group first
+fn group<F: Fn(&ReportCard) -> &String>(cards: &[ReportCard], key: F) -> Vec<Group>
+{
+    let mut groups: BTreeMap<String, Group> = BTreeMap::new();
+
+    for card in cards
+    {
+        let name = key(card).clone();
+        let group = groups.entry(name.clone()).or_insert(Group { name, count: 0, value: 0.0 });
+
+        group.count += 1;
+        group.value += card.market_value;
+    }
+
+    let mut groups: Vec<Group> = groups.into_values().collect();
+    groups.sort_by(|a, b| b.value.total_cmp(&a.value).then(a.name.cmp(&b.name)));
+
+    groups
+}
+
+fn or_unknown(text: String) -> String
+{
+    if text.is_empty()
+    {
+        "Unknown".to_string()
+    }
+    else
+    {
+        text
+    }
+}
+
+/// card text comes from tcgplayer as html, this turns it into plain text
+pub fn plain_text(description: &str) -> String
+{
+    let with_breaks = description.replace("<br>", "\n").replace("<br/>", "\n").replace("<br />", "\n");
+
+    let mut text = String::new();
+    let mut in_tag = false;
+
+    for character in with_breaks.chars()
+    {
+        match character
+        {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(character),
+            _ => ()
+        }
+    }
+
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
@@ -0,0 +1,495 @@
+//! `serve`, a small web server for looking at and changing the collection from a browser
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::currency;
+use crate::html;
+use crate::journal;
+use crate::ledger::{self, Disposal, DisposalKind};
+use crate::settings::{self, ReportMode};
+use crate::stats;
+use crate::util::{self, format_id, Card, Database};
+use crate::valuation::{self, Condition};
+
+/// where the dashboard listens unless told otherwise
+pub const DEFAULT_PORT: u16 = 8080;
+/// only this computer can reach the server unless it's given the address it has on the network
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1";
+/// requests that change the collection need this header set to the token printed when the server starts
+pub const TOKEN_HEADER: &str = "X-Session-Token";
+
+// the largest body a request can send, far more than any card the api takes
+const BODY_LIMIT: u64 = 64 * 1024;
+
+// what one request needs to work with, kept between requests
+struct State
+{
+    db: Database,
+    webcache: HashMap<String, String>,
+    history: journal::History,
+    thumbnails: HashMap<String, Vec<u8>>,
+    db_path: String,
+    cache_path: String,
+    journal_path: String,
+    // the Host headers a request may have, anything else came through a dns name pointed at this computer
+    hosts: Vec<String>,
+    // new every time the server starts, only the dashboard page and whoever started the server know it
+    token: String
+}
+
+// what POST /api/cards takes, everything but the product id can be left out
+#[derive(serde::Deserialize)]
+struct NewCard
+{
+    product_id: serde_json::Number,
+    #[serde(default = "default_quantity")]
+    quantity: u32,
+    collection: Option<String>,
+    #[serde(default)]
+    owner: String,
+    #[serde(default)]
+    condition: Condition
+}
+
+fn default_quantity() -> u32
+{
+    1
+}
+
+// what DELETE /api/cards/<index> takes, the card that should be at that position
+#[derive(serde::Deserialize)]
+struct ExpectedCard
+{
+    product_id: serde_json::Number,
+    condition: Condition
+}
+
+// 32 hex characters straight from the os random number generator, None when it has nothing to give
+fn new_token() -> Option<String>
+{
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).ok()?;
+
+    Some(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// serves db on address, which is saved back to db_path as it changes, only coming back when the server can't start
+///
+/// runs in the foreground, printing where it's listening and the session token once it's up
+pub fn serve(address: &str, port: u16, db: Database, db_path: &str, cache_path: &str, journal_path: &str) -> Result<(), String>
+{
+    let server = match Server::http((address, port))
+    {
+        Ok(server) => server,
+        Err(error) => return Err(format!("could not start the server on {}:{}! {}", address, port, error))
+    };
+
+    let token = new_token().ok_or("could not get a random session token from the os!")?;
+
+    let mut state = State {
+        db,
+        webcache: util::import_cache(cache_path),
+        history: journal::History::default(),
+        thumbnails: HashMap::new(),
+        db_path: db_path.to_string(),
+        cache_path: cache_path.to_string(),
+        journal_path: journal_path.to_string(),
+        hosts: allowed_hosts(address, port),
+        token
+    };
+
+    if address == DEFAULT_ADDRESS
+    {
+        println!("[+] serving the collection on http://localhost:{}", port);
+    }
+    else
+    {
+        println!("[+] serving the collection on http://{}:{}", address, port);
+        println!("[+] anyone who can reach that address can open it, and change the collection too");
+    }
+
+    println!("[+] scripts changing the collection need the header {}: {}", TOKEN_HEADER, state.token);
+    println!("[-] press ctrl+c to stop");
+
+    for request in server.incoming_requests()
+    {
+        let cache_size = state.webcache.len();
+
+        handle(request, &mut state);
+
+        // only write the cache when something new was fetched, it gets big
+        if state.webcache.len() != cache_size
+        {
+            util::save_cache(state.webcache.clone(), &state.cache_path);
+        }
+    }
+
+    Ok(())
+}
+
+fn handle(mut request: Request, state: &mut State)
+{
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let query: HashMap<String, String> = url::form_urlencoded::parse(query.as_bytes()).into_owned().collect();
+
+    // one byte over the limit is enough to know the body is too big
+    let mut body = String::new();
+    let read = request.as_reader().take(BODY_LIMIT + 1).read_to_string(&mut body);
+
+    if read.is_err()
+    {
+        let _ = request.respond(error(400, "the request body could not be read"));
+        return;
+    }
+
+    if body.len() as u64 > BODY_LIMIT
+    {
+        let _ = request.respond(error(413, "the request body is too big"));
+        return;
+    }
+
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    // a page on another site can point its own dns name at this computer, but the browser still sends that name
+    if !has_header(&request, "Host", |value| state.hosts.iter().any(|host| host.eq_ignore_ascii_case(value)))
+    {
+        let _ = request.respond(error(403, "open the server with the address it printed when it started"));
+        return;
+    }
+
+    // other sites can't set either header without the browser asking first, which gets a 404
+    if matches!(request.method(), Method::Post | Method::Delete)
+    {
+        if !has_header(&request, "Content-Type", |value| value.starts_with("application/json"))
+        {
+            let _ = request.respond(error(415, "send the card as application/json"));
+            return;
+        }
+
+        if !has_header(&request, TOKEN_HEADER, |value| value == state.token)
+        {
+            let _ = request.respond(error(403, &format!("changing the collection needs the {} header printed when the server started", TOKEN_HEADER)));
+            return;
+        }
+    }
+
+    let response = match (request.method(), segments.as_slice())
+    {
+        (Method::Get, [""]) => report_page(state),
+        (Method::Get, ["manage"]) => dashboard_page(state),
+        (Method::Get, ["images", image]) => card_image(state, image),
+        (Method::Get, ["api", "cards"]) => list_cards(state),
+        (Method::Post, ["api", "cards"]) => add_cards(state, &body),
+        (Method::Delete, ["api", "cards", index]) => remove_card(state, index, &body),
+        (Method::Get, ["api", "valuation"]) => valuation(state),
+        (Method::Get, ["api", "stats"]) => stats(state),
+        (Method::Get, ["api", "search"]) => search(state, &query),
+        _ => error(404, "nothing here")
+    };
+
+    let _ = request.respond(response);
+}
+
+// localhost and the address the server was started on, with and without the port
+fn allowed_hosts(address: &str, port: u16) -> Vec<String>
+{
+    let address = if address.contains(':') { format!("[{}]", address) } else { address.to_string() };
+
+    ["localhost", "127.0.0.1", "[::1]", address.as_str()].iter()
+        .flat_map(|host| [host.to_string(), format!("{}:{}", host, port)])
+        .collect()
+}
+
+fn has_header(request: &Request, name: &'static str, matches: impl Fn(&str) -> bool) -> bool
+{
+    request.headers().iter().any(|header| header.field.equiv(name) && matches(header.value.as_str().trim()))
+}
+
+fn header(value: &str) -> Header
+{
+    Header::from_bytes("Content-Type", value).unwrap()
+}
+
+fn json_response(status: u16, value: &Value) -> Response<std::io::Cursor<Vec<u8>>>
+{
+    Response::from_string(value.to_string()).with_status_code(status).with_header(header("application/json"))
+}
+
+fn error(status: u16, message: &str) -> Response<std::io::Cursor<Vec<u8>>>
+{
+    json_response(status, &json!({ "error": message }))
+}
+
+fn html_response(html_code: String) -> Response<std::io::Cursor<Vec<u8>>>
+{
+    Response::from_string(html_code).with_header(header("text/html; charset=utf-8"))
+}
+
+// the normal report, with images served by the server instead of packed into the page
+fn report_page(state: &mut State) -> Response<std::io::Cursor<Vec<u8>>>
+{
+    let mut settings = settings::load();
+
+    if settings.report_mode == ReportMode::Embedded
+    {
+        settings.report_mode = ReportMode::Folder;
+    }
+
+    let page_request = html::page(&state.db, state.webcache.clone(), &settings, &mut state.thumbnails);
+    state.webcache = page_request.1;
+
+    match page_request.0
+    {
+        Ok(html_code) => html_response(html_code),
+        Err(message) => error(500, &message)
+    }
+}
+
+fn dashboard_page(state: &State) -> Response<std::io::Cursor<Vec<u8>>>
+{
+    match html::dashboard(&settings::load(), &state.token)
+    {
+        Ok(html_code) => html_response(html_code),
+        Err(message) => error(500, &message)
+    }
+}
+
+fn card_image(state: &mut State, image: &str) -> Response<std::io::Cursor<Vec<u8>>>
+{
+    let product_id = image.trim_end_matches(".jpg");
+
+    if product_id.is_empty() || !product_id.chars().all(|character| character.is_ascii_digit())
+    {
+        return error(404, "no such image");
+    }
+
+    if !state.thumbnails.contains_key(product_id)
+    {
+        let image_request = util::card_image_b64(product_id, state.webcache.clone());
+        state.webcache = image_request.1;
+
+        state.thumbnails.insert(product_id.to_string(), crate::images::thumbnail_or_original(&image_request.0));
+    }
+
+    Response::from_data(state.thumbnails[product_id].clone()).with_header(header("image/jpeg"))
+}
+
+// every card with its position, which DELETE /api/cards/<index> takes along with the product id and condition
+fn list_cards(state: &mut State) -> Response<std::io::Cursor<Vec<u8>>>
+{
+    let settings = settings::load();
+
+    let valuation_request = valuation::value(&state.db, state.webcache.clone(), &settings);
+    state.webcache = valuation_request.1;
+
+    let cards: Vec<Value> = valuation_request.0.cards.iter()
+        .map(|card_value| {
+            let card = &state.db.cards[card_value.index];
+
+            json!({
+                "index": card_value.index,
+                "product_id": card.product_id,
+                "name": card_value.name,
+                "set_name": card_value.set_name,
+                "collection": card.collection,
+                "owner": card.owner,
+                "condition": card.condition,
+                "condition_name": valuation::condition_name(card.condition),
+                "unit_price": card_value.unit_price,
+                "value": card_value.value
+            })
+        })
+        .collect();
+
+    json_response(200, &json!({ "cards": cards, "currency": currency::load(&settings) }))
+}
+
+fn add_cards(state: &mut State, body: &str) -> Response<std::io::Cursor<Vec<u8>>>
+{
+    let new_card: NewCard = match serde_json::from_str(body)
+    {
+        Ok(new_card) => new_card,
+        Err(error_message) => return error(400, &format!("could not read the card: {}", error_message))
+    };
+
+    if new_card.quantity == 0
+    {
+        return error(400, "quantity has to be at least 1");
+    }
+
+    let collection = new_card.collection.unwrap_or(crate::collection::target(&state.db));
+
+    if !state.db.collections.contains(&collection)
+    {
+        return error(400, &format!("there is no collection called {}", collection));
+    }
+
+    if !new_card.owner.is_empty() && !state.db.owners.contains(&new_card.owner)
+    {
+        return error(400, &format!("there is no owner called {}", new_card.owner));
+    }
+
+    let before = state.db.clone();
+
+    for _ in 0..new_card.quantity
+    {
+        state.db.cards.push(Card { product_id: new_card.product_id.clone(), collection: collection.clone(), owner: new_card.owner.clone(), condition: new_card.condition });
+    }
+
+    if !save(state, &before)
+    {
+        return error(500, "the database could not be saved");
+    }
+
+    let added: Vec<usize> = (before.cards.len()..state.db.cards.len()).collect();
+
+    json_response(201, &json!({ "added": added }))
+}
+
+fn remove_card(state: &mut State, index: &str, body: &str) -> Response<std::io::Cursor<Vec<u8>>>
+{
+    let index = match index.parse::<usize>()
+    {
+        Ok(index) if index < state.db.cards.len() => index,
+        _ => return error(404, "no card at that position")
+    };
+
+    let expected: ExpectedCard = match serde_json::from_str(body)
+    {
+        Ok(expected) => expected,
+        Err(error_message) => return error(400, &format!("say which card should be at that position, eg. {{\"product_id\": 1234, \"condition\": \"NearMint\"}}: {}", error_message))
+    };
+
+    // positions move when cards are added or removed elsewhere, so a page that hasn't reloaded could point at another card
+    if format_id(expected.product_id) != format_id(state.db.cards[index].product_id.clone()) || expected.condition != state.db.cards[index].condition
+    {
+        return error(409, "the collection has changed since that position was read, reload and try again");
+    }
+
+    let product_id = format_id(state.db.cards[index].product_id.clone());
+    let product_request = util::get_product_details(&product_id, state.webcache.clone());
+    state.webcache = product_request.1;
+
+    let product_info: Value = serde_json::from_str(&product_request.0).unwrap_or_default();
+
+    let disposal = Disposal {
+        card: state.db.cards[index].clone(),
+        name: util::display_name(&product_info),
+        kind: DisposalKind::Removed,
+        date: chrono::Local::now().date_naive(),
+        sale_price: 0.0,
+        fees: 0.0,
+        shipping: 0.0,
+        counterparty: String::new(),
+        purchase_cost: None
+    };
+
+    let before = state.db.clone();
+
+    ledger::archive(&mut state.db, index, disposal);
+
+    if !save(state, &before)
+    {
+        return error(500, "the database could not be saved");
+    }
+
+    json_response(200, &json!({ "removed": index }))
+}
+
+// puts the database back the way it was when saving fails, so the server never shows changes that aren't on disk
+fn save(state: &mut State, before: &Database) -> bool
+{
+    if !util::save_db(state.db.clone(), &state.db_path)
+    {
+        state.db = before.clone();
+        return false;
+    }
+
+    journal::record(&mut state.history, before, &state.db, &state.journal_path);
+
+    true
+}
+
+fn valuation(state: &mut State) -> Response<std::io::Cursor<Vec<u8>>>
+{
+    let settings = settings::load();
+
+    let valuation_request = valuation::value(&state.db, state.webcache.clone(), &settings);
+    state.webcache = valuation_request.1;
+
+    let valuation = valuation_request.0;
+
+    json_response(200, &json!({
+        "basis": valuation::basis_name(valuation.basis),
+        "total": valuation.total,
+        "card_count": valuation.card_count,
+        "unknown": valuation.unknown,
+        "sets": valuation.sets,
+        "cards": valuation.cards,
+        "currency": currency::load(&settings)
+    }))
+}
+
+fn stats(state: &mut State) -> Response<std::io::Cursor<Vec<u8>>>
+{
+    // the server shows every collection, so the snapshot isn't narrowed to the active one
+    let mut db = state.db.clone();
+    db.active = None;
+
+    let stats_request = stats::gather(&db, state.webcache.clone(), &settings::load());
+    state.webcache = stats_request.1;
+
+    json_response(200, &json!(stats_request.0))
+}
+
+// a page of tcgplayer's search cut down to one piece cards, ready to POST back to /api/cards
+// takes q, page and any of the search filters, eg. ?q=luffy&set=romance-dawn&max=5
+fn search(state: &mut State, query: &HashMap<String, String>) -> Response<std::io::Cursor<Vec<u8>>>
+{
+    let card_name = query.get("q").map(|q| q.as_str()).unwrap_or_default();
+
+    if card_name.trim().is_empty()
+    {
+        return error(400, "search for something with ?q=");
+    }
+
+    let page: usize = query.get("page").and_then(|page| page.parse().ok()).unwrap_or_default();
+    let mut filters = util::SearchFilters::default();
+
+    for name in util::SEARCH_FILTER_NAMES
+    {
+        if let Some(value) = query.get(name)
+        {
+            if !util::search_filter(&mut filters, name, value)
+            {
+                return error(400, &format!("{} is not a price", value));
+            }
+        }
+    }
+
+    let search_request = util::search_page(card_name, page, &filters, state.webcache.clone());
+    state.webcache = search_request.1;
+
+    let response: Value = match serde_json::from_str(&search_request.0)
+    {
+        Ok(response) => response,
+        Err(_) => return error(502, "could not search tcgplayer")
+    };
+
+    let results: Vec<Value> = response["results"][0]["results"].as_array().cloned().unwrap_or_default().iter()
+        .filter(|product| product["productLineName"].as_str() == Some(util::PRODUCT_LINE) && !product["customAttributes"]["number"].as_str().unwrap_or_default().is_empty())
+        .map(|product| json!({
+            "product_id": product["productId"],
+            "name": util::display_name(product),
+            "set_name": util::set_name(product),
+            "market_price": product["marketPrice"]
+        }))
+        .collect();
+
+    json_response(200, &json!({ "results": results, "page": page, "pages": util::search_pages(&response) }))
+}
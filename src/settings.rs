@@ -0,0 +1,115 @@
+//! user settings, kept in the config directory along with custom report templates and themes
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::util;
+use crate::valuation::{Condition, PriceBasis};
+
+/// set to a folder to keep the settings, rates and templates there instead of the usual config directory
+pub const CONFIG_DIR_VAR: &str = "ONEPIECECARDS_CONFIG_DIR";
+
+/// everything the user can change from the settings menu
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Settings
+{
+    /// "dark", "light", or the name of a css file in the themes folder
+    #[serde(default = "default_theme")]
+    pub theme: String,
+    /// how the report gets its card images
+    #[serde(default)]
+    pub report_mode: ReportMode,
+    /// which tcgplayer price cards are valued at
+    #[serde(default)]
+    pub price_basis: PriceBasis,
+    /// only the conditions the user changed, the rest use the defaults
+    #[serde(default)]
+    pub condition_multipliers: BTreeMap<Condition, f64>,
+    /// what prices are shown in, eg. "EUR"
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    /// off keeps using rates.json in the config directory as it is, for a hand made rate table
+    #[serde(default = "default_fetch_rates")]
+    pub fetch_rates: bool
+}
+
+/// how the report gets its card images
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum ReportMode
+{
+    /// one html file with the images inside it
+    #[default]
+    Embedded,
+    /// a folder with the html and the image files next to it
+    Folder,
+    /// images load from tcgplayer's cdn, smallest file but needs the internet
+    Cdn
+}
+
+/// eg. "self-contained", as the settings menu shows it
+pub fn report_mode_name(mode: ReportMode) -> &'static str
+{
+    match mode
+    {
+        ReportMode::Embedded => "self-contained",
+        ReportMode::Folder => "report folder",
+        ReportMode::Cdn => "cdn links"
+    }
+}
+
+fn default_theme() -> String
+{
+    "dark".to_string()
+}
+
+fn default_currency() -> String
+{
+    crate::currency::BASE_CURRENCY.to_string()
+}
+
+fn default_fetch_rates() -> bool
+{
+    true
+}
+
+/// eg. ~/.config/onepiececards or %APPDATA%\onepiececards, or wherever CONFIG_DIR_VAR points
+pub fn config_dir() -> PathBuf
+{
+    match std::env::var_os(CONFIG_DIR_VAR)
+    {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => dirs::config_dir().unwrap_or_default().join("onepiececards")
+    }
+}
+
+fn settings_path() -> PathBuf
+{
+    config_dir().join("settings.json")
+}
+
+/// the saved settings, or the defaults when there are none yet
+pub fn load() -> Settings
+{
+    serde_json::from_str(&util::read_file(&settings_path().to_string_lossy())).unwrap_or(Settings {
+        theme: default_theme(),
+        report_mode: ReportMode::default(),
+        price_basis: PriceBasis::default(),
+        condition_multipliers: BTreeMap::new(),
+        currency: default_currency(),
+        fetch_rates: default_fetch_rates()
+    })
+}
+
+/// false when the settings couldn't be written
+pub fn save(settings: &Settings) -> bool
+{
+    if std::fs::create_dir_all(config_dir()).is_err()
+    {
+        return false;
+    }
+
+    match serde_json::to_string_pretty(settings)
+    {
+        Ok(json) => util::write_file(&settings_path().to_string_lossy(), &json),
+        Err(_) => false
+    }
+}
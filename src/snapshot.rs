@@ -0,0 +1,149 @@
+//! the collection frozen together with what every card was worth at the time, for comparing later
+
+use std::{collections::{BTreeMap, HashMap}, fs, path::PathBuf};
+
+use chrono::{DateTime, Local, TimeZone};
+
+use crate::backup;
+use crate::settings::Settings;
+use crate::util::{self, format_id, Database};
+use crate::valuation::{self, PriceBasis};
+
+const SNAPSHOT_DIR: &str = "snapshots";
+const STAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// the collection at a point in time, with what it was worth then
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Snapshot
+{
+    /// when it was taken
+    pub date: DateTime<Local>,
+    /// the whole database as it was
+    pub db: Database,
+    /// one copy's price per product id before condition, empty for backups which never stored prices
+    #[serde(default)]
+    pub prices: BTreeMap<String, f64>,
+    /// which price the prices are, market for backups
+    #[serde(default)]
+    pub basis: PriceBasis
+}
+
+/// something a diff can be taken against, newest first when listed
+pub struct Source
+{
+    /// eg. "snapshot 2024-06-01 10:00:00"
+    pub label: String,
+    /// the file it's kept in
+    pub path: PathBuf
+}
+
+/// the collection as it is right now, priced at today's prices
+pub fn current(db: &Database, mut webcache: HashMap<String, String>, settings: &Settings) -> (Snapshot, HashMap<String, String>)
+{
+    let mut prices: BTreeMap<String, f64> = BTreeMap::new();
+
+    for card in &db.cards
+    {
+        let product_id = format_id(card.product_id.clone());
+
+        if prices.contains_key(&product_id)
+        {
+            continue;
+        }
+
+        let price_request = valuation::price(&product_id, settings.price_basis, webcache);
+        webcache = price_request.1;
+
+        // unknown prices stay out so a later snapshot can still show them appearing
+        if let Some(price) = price_request.0
+        {
+            prices.insert(product_id, price);
+        }
+    }
+
+    (Snapshot { date: Local::now(), db: db.clone(), prices, basis: settings.price_basis }, webcache)
+}
+
+/// saves the collection at today's prices in the snapshot folder, None when it couldn't be written
+pub fn create(db: &Database, webcache: HashMap<String, String>, settings: &Settings) -> (Option<PathBuf>, HashMap<String, String>)
+{
+    let snapshot_request = current(db, webcache, settings);
+    let snapshot = snapshot_request.0;
+
+    if fs::create_dir_all(SNAPSHOT_DIR).is_err()
+    {
+        return (None, snapshot_request.1);
+    }
+
+    let path = PathBuf::from(SNAPSHOT_DIR).join(format!("snapshot-{}.json", snapshot.date.format(STAMP_FORMAT)));
+
+    let saved = match serde_json::to_string(&snapshot)
+    {
+        Ok(json) => util::write_file(&path.to_string_lossy(), &json),
+        Err(_) => false
+    };
+
+    (if saved { Some(path) } else { None }, snapshot_request.1)
+}
+
+/// the newest snapshot and its label, backups don't count since they never stored prices
+pub fn latest() -> Option<(Snapshot, String)>
+{
+    let entries = fs::read_dir(SNAPSHOT_DIR).ok()?;
+
+    let newest = entries.filter_map(|entry| entry.ok())
+        .filter_map(|entry| load(&entry.path()))
+        .filter(|snapshot| !snapshot.prices.is_empty())
+        .max_by_key(|snapshot| snapshot.date)?;
+
+    let label = format!("snapshot {}", newest.date.format("%Y-%m-%d %H:%M:%S"));
+
+    Some((newest, label))
+}
+
+/// snapshots and database backups together, newest first
+pub fn sources(db_path: &str) -> Vec<Source>
+{
+    let mut sources: Vec<(DateTime<Local>, Source)> = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(SNAPSHOT_DIR)
+    {
+        for entry in entries.filter_map(|entry| entry.ok())
+        {
+            if let Some(snapshot) = load(&entry.path())
+            {
+                let label = format!("snapshot {}", snapshot.date.format("%Y-%m-%d %H:%M:%S"));
+                sources.push((snapshot.date, Source { label, path: entry.path() }));
+            }
+        }
+    }
+
+    for found in backup::list(db_path)
+    {
+        if let Some(date) = Local.from_local_datetime(&found.date).single()
+        {
+            let label = format!("backup {}", found.date.format("%Y-%m-%d %H:%M:%S"));
+            sources.push((date, Source { label, path: found.path }));
+        }
+    }
+
+    sources.sort_by_key(|(date, _)| std::cmp::Reverse(*date));
+
+    sources.into_iter().map(|(_, source)| source).collect()
+}
+
+/// reads a snapshot, or a plain database backup as a snapshot without prices
+pub fn load(path: &PathBuf) -> Option<Snapshot>
+{
+    let contents = util::read_file(&path.to_string_lossy());
+
+    if let Ok(snapshot) = serde_json::from_str::<Snapshot>(&contents)
+    {
+        return Some(snapshot);
+    }
+
+    let db: Database = serde_json::from_str(&contents).ok()?;
+    let modified = fs::metadata(path).and_then(|metadata| metadata.modified()).ok()?;
+
+    Some(Snapshot { date: DateTime::<Local>::from(modified), db, prices: BTreeMap::new(), basis: PriceBasis::default() })
+}
@@ -0,0 +1,278 @@
+//! numbers about the collection as a whole: what it's made of, where the value sits, and what moved since the last snapshot
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::currency::{self, Currency};
+use crate::diff::{self, CardChange};
+use crate::gamedata;
+use crate::report::{self, ReportCard};
+use crate::settings::Settings;
+use crate::collection;
+use crate::snapshot;
+use crate::text::{self, Format};
+use crate::util::Database;
+
+/// how many cards the top products and the movers list stop at
+pub const TOP_COUNT: usize = 10;
+
+/// the cards sharing a set, rarity, color or card type
+#[derive(serde::Serialize, Clone)]
+pub struct Group
+{
+    /// the set, rarity, color or card type
+    pub name: String,
+    /// how many cards have it
+    pub count: u32,
+    /// what they're worth together
+    pub value: f64,
+    /// value per card
+    pub average: f64,
+    /// of the whole collection's value, 0 to 1
+    pub share: f64
+}
+
+/// how many copies sit at one cost on the curve
+#[derive(serde::Serialize, Clone)]
+pub struct Cost
+{
+    /// the card's cost
+    pub cost: u32,
+    /// copies at that cost
+    pub count: u32
+}
+
+/// one product with every copy held of it
+#[derive(serde::Serialize, Clone)]
+pub struct Holding
+{
+    /// the printing's tcgplayer id, formatted
+    pub product_id: String,
+    /// with the op code and pre release tag, what the menu shows
+    pub name: String,
+    /// copies held
+    pub quantity: u32,
+    /// every copy together, after condition multipliers
+    pub value: f64
+}
+
+/// the cards whose price moved most since a snapshot
+#[derive(serde::Serialize, Clone)]
+pub struct Movers
+{
+    /// the snapshot prices are compared with, eg. "snapshot 2024-06-01 10:00:00"
+    pub since: String,
+    /// the biggest price moves on cards held back then, by what they did to the copies held
+    pub cards: Vec<CardChange>
+}
+
+/// everything the stats page shows
+#[derive(serde::Serialize, Clone)]
+pub struct Stats
+{
+    /// what every card is worth together, in dollars
+    pub total: f64,
+    /// how many cards there are
+    pub card_count: u32,
+    /// what the values are based on, eg. "market price"
+    pub price_basis: String,
+    /// cards counted as nothing because they have no price
+    pub unknown_prices: u32,
+    /// every value is in dollars, this is what they're shown in
+    pub currency: Currency,
+    /// value by set
+    pub sets: Vec<Group>,
+    /// value by rarity
+    pub rarities: Vec<Group>,
+    /// value by color
+    pub colors: Vec<Group>,
+    /// value by card type
+    pub card_types: Vec<Group>,
+    /// copies at each cost, leaders and cards without a cost left out
+    pub cost_curve: Vec<Cost>,
+    /// the most valuable products by everything held of them
+    pub top: Vec<Holding>,
+    /// what the top products are worth together
+    pub top_value: f64,
+    /// their part of the total, 0 to 1
+    pub top_share: f64,
+    /// None without a snapshot to compare with
+    pub movers: Option<Movers>
+}
+
+/// everything stats shows for the database, prices compared with the newest snapshot if there is one
+///
+/// db is usually a collection::view, the snapshot is narrowed down to the same collection before comparing
+pub fn gather(db: &Database, webcache: HashMap<String, String>, settings: &Settings) -> (Stats, HashMap<String, String>)
+{
+    let data_request = report::gather(db, webcache, settings);
+    let data = data_request.0;
+    let mut webcache = data_request.1;
+
+    let cards: Vec<&ReportCard> = data.sets.iter().flat_map(|set| set.cards.iter()).collect();
+
+    let share = |value: f64| if data.total_worth > 0.0 { value / data.total_worth } else { 0.0 };
+
+    let groups = |groups: Vec<report::Group>| -> Vec<Group> {
+        groups.into_iter()
+            .map(|group| Group { average: group.value / group.count.max(1) as f64, share: share(group.value), name: group.name, count: group.count, value: group.value })
+            .collect()
+    };
+
+    // sets come most valuable first like the other groups, ties by name
+    let mut set_groups: Vec<report::Group> = data.sets.iter().map(|set| report::Group { name: set.name.clone(), count: set.count, value: set.subtotal }).collect();
+    set_groups.sort_by(|a, b| b.value.total_cmp(&a.value).then(a.name.cmp(&b.name)));
+
+    // cost curve, holdings by product
+    let mut costs: BTreeMap<u32, u32> = BTreeMap::new();
+    let mut holdings: Vec<Holding> = Vec::new();
+
+    for card in &cards
+    {
+        match holdings.iter_mut().find(|holding| holding.product_id == card.product_id)
+        {
+            Some(holding) => {
+                holding.quantity += 1;
+                holding.value += card.market_value;
+            },
+            None => holdings.push(Holding { product_id: card.product_id.clone(), name: card.name.clone(), quantity: 1, value: card.market_value })
+        }
+
+        let lookup_request = gamedata::lookup(&card.product_id, webcache);
+        webcache = lookup_request.1;
+
+        if let Some(cost) = lookup_request.0.cost
+        {
+            *costs.entry(cost).or_default() += 1;
+        }
+    }
+
+    // every cost up to the highest, so gaps in the curve show
+    let highest = costs.keys().last().copied().unwrap_or_default();
+    let cost_curve: Vec<Cost> = if costs.is_empty() { Vec::new() } else { (0..=highest).map(|cost| Cost { cost, count: costs.get(&cost).copied().unwrap_or_default() }).collect() };
+
+    holdings.sort_by(|a, b| b.value.total_cmp(&a.value).then(a.name.cmp(&b.name)));
+    holdings.truncate(TOP_COUNT);
+
+    let top_value: f64 = holdings.iter().map(|holding| holding.value).sum();
+
+    let movers = match snapshot::latest()
+    {
+        None => None,
+        Some((mut old, since)) => {
+            // the snapshot has every collection, cards outside this one would all look removed
+            old.db.active = db.active.clone();
+            old.db = collection::view(&old.db);
+
+            let current_request = snapshot::current(db, webcache, settings);
+            let diff_request = diff::compare(&old, &current_request.0, &since, "now", current_request.1, settings);
+            webcache = diff_request.1;
+
+            let mut cards = diff_request.0.repriced;
+            cards.truncate(TOP_COUNT);
+
+            Some(Movers { since, cards })
+        }
+    };
+
+    let stats = Stats {
+        total: data.total_worth,
+        card_count: data.card_count,
+        price_basis: data.price_basis.clone(),
+        unknown_prices: data.unknown_prices,
+        currency: data.currency.clone(),
+        sets: groups(set_groups),
+        rarities: groups(data.rarities.clone()),
+        colors: groups(data.colors.clone()),
+        card_types: groups(data.card_types.clone()),
+        cost_curve,
+        top: holdings,
+        top_value,
+        top_share: share(top_value),
+        movers
+    };
+
+    (stats, webcache)
+}
+
+fn percent(share: f64) -> String
+{
+    format!("{:.1}%", share * 100.0)
+}
+
+/// the stats as text tables, or markdown ones
+pub fn render(stats: &Stats, format: Format) -> String
+{
+    let money = |value: f64| currency::format(&stats.currency, value);
+
+    let mut output = String::new();
+
+    output.push_str(&text::heading("Collection Stats", 1, format));
+    output.push_str(&format!("Total Worth: {} ({} cards, by {})\n", money(stats.total), stats.card_count, stats.price_basis));
+
+    if stats.unknown_prices > 0
+    {
+        output.push_str(&format!("{} cards have no price and count as {}\n", stats.unknown_prices, money(0.0)));
+    }
+
+    if stats.currency.date.is_some()
+    {
+        output.push_str(&format!("{}\n", currency::rate_note(&stats.currency)));
+    }
+
+    output.push('\n');
+
+    for (title, groups) in [("By Set", &stats.sets), ("By Rarity", &stats.rarities), ("By Color", &stats.colors), ("By Card Type", &stats.card_types)]
+    {
+        let rows: Vec<Vec<String>> = groups.iter()
+            .map(|group| vec![group.name.clone(), group.count.to_string(), money(group.value), money(group.average), percent(group.share)])
+            .collect();
+
+        output.push_str(&text::heading(title, 2, format));
+        output.push_str(&text::table(&["Name", "Cards", "Value", "Average", "Share"], &rows, format));
+    }
+
+    output.push_str(&text::heading("Cost Curve", 2, format));
+
+    let tallest = stats.cost_curve.iter().map(|cost| cost.count).max().unwrap_or_default();
+
+    let cost_rows: Vec<Vec<String>> = stats.cost_curve.iter()
+        .map(|cost| {
+            // bars are at most 20 wide
+            let bar = if tallest > 0 { (cost.count * 20).div_ceil(tallest) } else { 0 };
+            vec![cost.cost.to_string(), cost.count.to_string(), "#".repeat(bar as usize)]
+        })
+        .collect();
+
+    output.push_str(&text::table(&["Cost", "Cards", ""], &cost_rows, format));
+
+    output.push_str(&text::heading(&format!("Top {} Cards ({} of the value, {})", TOP_COUNT, percent(stats.top_share), money(stats.top_value)), 2, format));
+
+    let top_rows: Vec<Vec<String>> = stats.top.iter()
+        .enumerate()
+        .map(|(i, holding)| vec![(i + 1).to_string(), holding.name.clone(), holding.quantity.to_string(), money(holding.value)])
+        .collect();
+
+    output.push_str(&text::table(&["#", "Card", "Qty", "Value"], &top_rows, format));
+
+    match &stats.movers
+    {
+        None => output.push_str("No snapshot yet, take one to see which prices move.\n"),
+        Some(movers) => {
+            output.push_str(&text::heading(&format!("Largest Movers Since {}", movers.since), 2, format));
+
+            if movers.cards.is_empty()
+            {
+                output.push_str("No prices have moved.\n");
+                return output;
+            }
+
+            let rows: Vec<Vec<String>> = movers.cards.iter()
+                .map(|change| vec![change.name.clone(), money(change.old_price), money(change.new_price), change.old_quantity.to_string(), currency::signed(&stats.currency, change.price_effect)])
+                .collect();
+
+            output.push_str(&text::table(&["Card", "Then", "Now", "Held", "Change"], &rows, format));
+        }
+    }
+
+    output
+}
@@ -0,0 +1,170 @@
+//! markdown and plain text versions of the report, for pasting into discord and github issues
+
+use crate::currency::{self, Currency};
+use crate::report::{Group, ReportData, ReportSet};
+use crate::valuation::{self, Condition};
+
+/// which flavour of text a report is written in
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format
+{
+    /// headings and tables for discord and github
+    Markdown,
+    /// plain lines for anywhere else
+    Text
+}
+
+/// the whole report in the given format, prices in the report's currency
+pub fn render(data: &ReportData, format: Format) -> String
+{
+    let mut output = String::new();
+
+    output.push_str(&heading("One Piece TCG Card List", 1, format));
+    output.push_str(&format!("Total Worth: {} ({} cards, by {})\n", currency::format(&data.currency, data.total_worth), data.card_count, data.price_basis));
+
+    if data.unknown_prices > 0
+    {
+        output.push_str(&format!("{} cards have no price and count as {}\n", data.unknown_prices, currency::format(&data.currency, 0.0)));
+    }
+
+    if data.currency.date.is_some()
+    {
+        output.push_str(&format!("{}\n", currency::rate_note(&data.currency)));
+    }
+
+    output.push('\n');
+
+    output.push_str(&heading("Most Valuable", 2, format));
+
+    let top_rows: Vec<Vec<String>> = data.top.iter()
+        .enumerate()
+        .map(|(i, card)| vec![(i + 1).to_string(), card.name.clone(), currency::format(&data.currency, card.market_value)])
+        .collect();
+
+    output.push_str(&table(&["#", "Card", "Value"], &top_rows, format));
+
+    for (title, groups) in [("By Set", set_groups(&data.sets)), ("By Rarity", data.rarities.clone()), ("By Color", data.colors.clone()), ("By Card Type", data.card_types.clone())]
+    {
+        output.push_str(&heading(title, 2, format));
+        output.push_str(&table(&["Name", "Cards", "Value"], &group_rows(&groups, &data.currency), format));
+    }
+
+    for set in &data.sets
+    {
+        output.push_str(&heading(&format!("{} ({} cards, {})", set.name, set.count, currency::format(&data.currency, set.subtotal)), 2, format));
+        output.push_str(&table(&["Qty", "Card", "Rarity", "Each", "Total"], &card_rows(set, &data.currency), format));
+    }
+
+    output
+}
+
+fn set_groups(sets: &[ReportSet]) -> Vec<Group>
+{
+    sets.iter().map(|set| Group { name: set.name.clone(), count: set.count, value: set.subtotal }).collect()
+}
+
+fn group_rows(groups: &[Group], currency: &Currency) -> Vec<Vec<String>>
+{
+    groups.iter().map(|group| vec![group.name.clone(), group.count.to_string(), currency::format(currency, group.value)]).collect()
+}
+
+// copies of the same card in the same condition share a row, since the condition changes what each one is worth
+fn card_rows(set: &ReportSet, currency: &Currency) -> Vec<Vec<String>>
+{
+    let mut rows: Vec<(u32, &crate::report::ReportCard)> = Vec::new();
+
+    for card in &set.cards
+    {
+        match rows.iter_mut().find(|(_, other)| other.product_id == card.product_id && other.condition == card.condition)
+        {
+            Some(row) => row.0 += 1,
+            None => rows.push((1, card))
+        }
+    }
+
+    rows.sort_by(|a, b| (b.1.market_value * b.0 as f64).total_cmp(&(a.1.market_value * a.0 as f64)).then(a.1.name.cmp(&b.1.name)));
+
+    rows.iter()
+        .map(|(quantity, card)| vec![quantity.to_string(), card_name(card), card.rarity.clone(), currency::format(currency, card.market_value), currency::format(currency, card.market_value * *quantity as f64)])
+        .collect()
+}
+
+// eg. "Nami OP01-016 (lightly played)", near mint goes without saying
+fn card_name(card: &crate::report::ReportCard) -> String
+{
+    if card.condition == valuation::condition_name(Condition::NearMint)
+    {
+        card.name.clone()
+    }
+    else
+    {
+        format!("{} ({})", card.name, card.condition)
+    }
+}
+
+pub(crate) fn heading(title: &str, level: usize, format: Format) -> String
+{
+    match format
+    {
+        Format::Markdown => format!("{} {}\n\n", "#".repeat(level), title),
+        Format::Text => {
+            let underline = if level == 1 { "=" } else { "-" };
+            format!("{}\n{}\n\n", title, underline.repeat(title.chars().count()))
+        }
+    }
+}
+
+pub(crate) fn table(headers: &[&str], rows: &[Vec<String>], format: Format) -> String
+{
+    let mut output = String::new();
+
+    match format
+    {
+        Format::Markdown => {
+            output.push_str(&format!("| {} |\n", headers.join(" | ")));
+            output.push_str(&format!("|{}|\n", headers.iter().map(|_| "---").collect::<Vec<&str>>().join("|")));
+
+            for row in rows
+            {
+                let cells: Vec<String> = row.iter().map(|cell| cell.replace('|', "\\|")).collect();
+                output.push_str(&format!("| {} |\n", cells.join(" | ")));
+            }
+        },
+        Format::Text => {
+            let mut widths: Vec<usize> = headers.iter().map(|header| header.chars().count()).collect();
+
+            for row in rows
+            {
+                for (i, cell) in row.iter().enumerate()
+                {
+                    widths[i] = widths[i].max(cell.chars().count());
+                }
+            }
+
+            let line = |cells: Vec<String>| -> String {
+                cells.iter()
+                    .enumerate()
+                    .map(|(i, cell)| format!("{}{}", cell, " ".repeat(widths[i] - cell.chars().count())))
+                    .collect::<Vec<String>>()
+                    .join("  ")
+                    .trim_end()
+                    .to_string()
+            };
+
+            output.push_str(&line(headers.iter().map(|header| header.to_string()).collect()));
+            output.push('\n');
+            output.push_str(&line(widths.iter().map(|width| "-".repeat(*width)).collect()));
+            output.push('\n');
+
+            for row in rows
+            {
+                output.push_str(&line(row.clone()));
+                output.push('\n');
+            }
+        }
+    }
+
+    output.push('\n');
+
+    output
+}
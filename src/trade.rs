@@ -0,0 +1,188 @@
+//! who owns which card, and trades between owners
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local};
+use serde_json::{Number, Value};
+
+use crate::settings::Settings;
+use crate::util::{self, format_id, Card, Database};
+use crate::valuation;
+
+/// cards swapping hands between two owners, recorded when it goes through
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Trade
+{
+    /// when it went through
+    pub date: DateTime<Local>,
+    /// the owner who proposed it
+    pub first_owner: String,
+    /// the other side
+    pub second_owner: String,
+    /// what the first owner handed over
+    pub first_gives: Vec<TradeCard>,
+    /// what the second owner handed over
+    pub second_gives: Vec<TradeCard>
+}
+
+/// the value is kept so old trades still show what they were worth at the time
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct TradeCard
+{
+    /// the printing's tcgplayer id
+    pub product_id: Number,
+    /// with the op code and pre release tag, what the menu shows
+    pub name: String,
+    /// in dollars, at the price basis and condition multiplier used when the trade was proposed
+    pub value: f64
+}
+
+/// "-" for cards nobody owns, otherwise the owner as is
+pub fn owner_name(owner: &str) -> &str
+{
+    if owner.is_empty()
+    {
+        "-"
+    }
+    else
+    {
+        owner
+    }
+}
+
+/// false when the name is empty or already taken
+pub fn create_owner(db: &mut Database, name: &str) -> bool
+{
+    let name = name.trim();
+
+    if name.is_empty() || db.owners.iter().any(|owner| owner == name)
+    {
+        return false;
+    }
+
+    db.owners.push(name.to_string());
+
+    true
+}
+
+/// false when the index or the owner doesn't exist
+pub fn set_owner(db: &mut Database, index: usize, owner: &str) -> bool
+{
+    if index >= db.cards.len() || !db.owners.iter().any(|name| name == owner)
+    {
+        return false;
+    }
+
+    db.cards[index].owner = owner.to_string();
+
+    true
+}
+
+/// indices into db.cards for everything one owner holds
+pub fn holdings(db: &Database, owner: &str) -> Vec<usize>
+{
+    db.cards.iter()
+        .enumerate()
+        .filter(|(_, card)| card.owner == owner)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// what a side of a trade is worth together
+pub fn total(cards: &[TradeCard]) -> f64
+{
+    cards.iter().map(|card| card.value).sum()
+}
+
+/// one card valued the way the rest of the collection is, a card without a price counts as nothing
+pub fn trade_card(card: &Card, product_info: &Value, settings: &Settings) -> TradeCard
+{
+    let unit_price = valuation::unit_price(product_info, settings.price_basis).unwrap_or(0.0);
+
+    TradeCard {
+        product_id: card.product_id.clone(),
+        name: util::display_name(product_info),
+        value: unit_price * valuation::multiplier(card.condition, settings)
+    }
+}
+
+/// prices up both sides of a trade without touching the database, None if a card isn't the owner's to give
+pub fn propose(db: &Database, first_owner: &str, second_owner: &str, first_indices: &[usize], second_indices: &[usize], settings: &Settings, mut webcache: HashMap<String, String>) -> (Option<Trade>, HashMap<String, String>)
+{
+    if first_owner == second_owner || !valid_side(db, first_owner, first_indices) || !valid_side(db, second_owner, second_indices)
+    {
+        return (None, webcache);
+    }
+
+    let mut sides: Vec<Vec<TradeCard>> = Vec::new();
+
+    for indices in [first_indices, second_indices]
+    {
+        let mut side: Vec<TradeCard> = Vec::new();
+
+        for index in indices
+        {
+            let product_request = util::get_product_details(&format_id(db.cards[*index].product_id.clone()), webcache.clone());
+            webcache = product_request.1;
+
+            let product_info: Value = serde_json::from_str(&product_request.0).unwrap_or_default();
+
+            side.push(trade_card(&db.cards[*index], &product_info, settings));
+        }
+
+        sides.push(side);
+    }
+
+    let second_gives = sides.pop().unwrap_or_default();
+    let first_gives = sides.pop().unwrap_or_default();
+
+    let trade = Trade {
+        date: Local::now(),
+        first_owner: first_owner.to_string(),
+        second_owner: second_owner.to_string(),
+        first_gives,
+        second_gives
+    };
+
+    (Some(trade), webcache)
+}
+
+/// swaps every card in one go and records the trade, nothing changes if any card moved since it was proposed
+pub fn execute(db: &mut Database, trade: Trade, first_indices: &[usize], second_indices: &[usize]) -> bool
+{
+    if !valid_side(db, &trade.first_owner, first_indices) || !valid_side(db, &trade.second_owner, second_indices)
+    {
+        return false;
+    }
+
+    for index in first_indices
+    {
+        db.cards[*index].owner = trade.second_owner.clone();
+    }
+
+    for index in second_indices
+    {
+        db.cards[*index].owner = trade.first_owner.clone();
+    }
+
+    db.trades.push(trade);
+
+    true
+}
+
+fn valid_side(db: &Database, owner: &str, indices: &[usize]) -> bool
+{
+    let mut seen: Vec<usize> = Vec::new();
+
+    for index in indices
+    {
+        if *index >= db.cards.len() || db.cards[*index].owner != owner || seen.contains(index)
+        {
+            return false;
+        }
+
+        seen.push(*index);
+    }
+
+    true
+}
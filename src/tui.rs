@@ -0,0 +1,735 @@
+//! full screen collection browser, `tui` on the command line, for quickly looking through and adding to the collection
+
+use std::{collections::{BTreeMap, HashMap}, time::{Duration, Instant}};
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, List, ListItem, ListState, Paragraph, Row, Table, TableState, Wrap},
+    DefaultTerminal, Frame
+};
+use serde_json::Value;
+
+use crate::collection;
+use crate::currency::{self, Currency};
+use crate::html;
+use crate::index;
+use crate::journal;
+use crate::ledger::{self, Disposal, DisposalKind};
+use crate::report;
+use crate::settings::{self, Settings};
+use crate::util::{self, format_id, Card, Database};
+use crate::valuation::{self, Condition};
+
+// searching waits for typing to pause so every keystroke isn't a request to tcgplayer
+const SEARCH_DELAY: Duration = Duration::from_millis(400);
+const PAGE_SIZE: usize = 20;
+
+// copies of the same card in the same condition, collection and owner share a row
+struct Holding
+{
+    product_id: String,
+    condition: Condition,
+    collection: String,
+    owner: String,
+    // positions in db.cards
+    indices: Vec<usize>,
+    name: String,
+    set_name: String,
+    unit_price: Option<f64>,
+    // one copy after the condition multiplier
+    value: f64
+}
+
+struct SearchResult
+{
+    product_id: serde_json::Number,
+    name: String,
+    set_name: String,
+    market_price: Option<f64>
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SortColumn
+{
+    Name,
+    Set,
+    Quantity,
+    Each,
+    Total
+}
+
+#[derive(PartialEq)]
+enum Mode
+{
+    Browse,
+    Search,
+    // typing a new quantity for the selected holding
+    Quantity(String),
+    // waiting for y/n before removing every copy of the selected holding
+    ConfirmRemove
+}
+
+struct App
+{
+    db: Database,
+    webcache: HashMap<String, String>,
+    history: journal::History,
+    settings: Settings,
+    currency: Currency,
+    // parsed product details, so redrawing never touches json
+    products: HashMap<String, Value>,
+    holdings: Vec<Holding>,
+    table: TableState,
+    sort: SortColumn,
+    descending: bool,
+    mode: Mode,
+    search_input: String,
+    typed_at: Option<Instant>,
+    // cards already looked up, searched on every keystroke while tcgplayer waits
+    index: Vec<index::Entry>,
+    results: Vec<SearchResult>,
+    result_list: ListState,
+    status: String,
+    db_path: String,
+    journal_path: String
+}
+
+/// takes over the terminal until q is pressed, saving every change to db_path and journaling it to journal_path
+///
+/// the database comes back as it was left, or why the terminal gave out part way
+pub fn run(db: Database, webcache: HashMap<String, String>, db_path: &str, journal_path: &str) -> (Result<Database, String>, HashMap<String, String>)
+{
+    let settings = settings::load();
+
+    let mut app = App {
+        db,
+        webcache,
+        history: journal::History::default(),
+        currency: currency::load(&settings),
+        settings,
+        products: HashMap::new(),
+        holdings: Vec::new(),
+        table: TableState::default(),
+        sort: SortColumn::Name,
+        descending: false,
+        mode: Mode::Browse,
+        search_input: String::new(),
+        typed_at: None,
+        index: Vec::new(),
+        results: Vec::new(),
+        result_list: ListState::default(),
+        status: "? for keys".to_string(),
+        db_path: db_path.to_string(),
+        journal_path: journal_path.to_string()
+    };
+
+    rebuild(&mut app);
+
+    // built once here, then searches add to it as they come in
+    let index_request = index::build(&app.db, app.webcache.clone());
+    app.webcache = index_request.1;
+    app.index = index_request.0;
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+
+    (result.map(|()| app.db).map_err(|error| format!("the terminal ui stopped! {}", error)), app.webcache)
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> std::io::Result<()>
+{
+    loop
+    {
+        terminal.draw(|frame| draw(frame, app))?;
+
+        // the details panel says it's loading until this fetch comes back and the next draw shows them
+        if let Some(product_id) = detail_product_id(app).filter(|product_id| !app.products.contains_key(product_id))
+        {
+            product(app, &product_id);
+            continue;
+        }
+
+        if let Some(typed_at) = app.typed_at
+        {
+            if typed_at.elapsed() >= SEARCH_DELAY
+            {
+                app.typed_at = None;
+                search(app);
+                continue;
+            }
+        }
+
+        if !event::poll(Duration::from_millis(100))?
+        {
+            continue;
+        }
+
+        let key = match event::read()?
+        {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue
+        };
+
+        let keep_going = match app.mode
+        {
+            Mode::Browse => browse_key(app, key.code),
+            Mode::Search => search_key(app, key.code),
+            Mode::Quantity(_) => quantity_key(app, key.code),
+            Mode::ConfirmRemove => confirm_key(app, key.code)
+        };
+
+        if !keep_going
+        {
+            return Ok(());
+        }
+    }
+}
+
+// false quits
+fn browse_key(app: &mut App, code: KeyCode) -> bool
+{
+    match code
+    {
+        KeyCode::Char('q') | KeyCode::Esc => return false,
+        KeyCode::Down | KeyCode::Char('j') => move_selection(app, 1),
+        KeyCode::Up | KeyCode::Char('k') => move_selection(app, -1),
+        KeyCode::PageDown => move_selection(app, PAGE_SIZE as isize),
+        KeyCode::PageUp => move_selection(app, -(PAGE_SIZE as isize)),
+        KeyCode::Home => app.table.select(Some(0)),
+        KeyCode::End => app.table.select(Some(app.holdings.len().saturating_sub(1))),
+        KeyCode::Char('s') => {
+            app.sort = match app.sort
+            {
+                SortColumn::Name => SortColumn::Set,
+                SortColumn::Set => SortColumn::Quantity,
+                SortColumn::Quantity => SortColumn::Each,
+                SortColumn::Each => SortColumn::Total,
+                SortColumn::Total => SortColumn::Name
+            };
+            sort(app);
+        },
+        KeyCode::Char('S') => {
+            app.descending = !app.descending;
+            sort(app);
+        },
+        KeyCode::Char('a') | KeyCode::Char('/') | KeyCode::Tab => app.mode = Mode::Search,
+        KeyCode::Char('+') | KeyCode::Char('=') => change_quantity(app, 1),
+        KeyCode::Char('-') => change_quantity(app, -1),
+        KeyCode::Char('e') if selected(app).is_some() => app.mode = Mode::Quantity(String::new()),
+        KeyCode::Char('d') | KeyCode::Delete if selected(app).is_some() => app.mode = Mode::ConfirmRemove,
+        KeyCode::Char('r') => generate_report(app),
+        KeyCode::Char('u') => {
+            let restored = journal::undo(&mut app.history, &app.db, &app.journal_path);
+            restore(app, restored, "undo");
+        },
+        KeyCode::Char('U') => {
+            let restored = journal::redo(&mut app.history, &app.db, &app.journal_path);
+            restore(app, restored, "redo");
+        },
+        KeyCode::Char('?') => app.status = "a search  +/- copies  e quantity  d remove  s sort  S reverse  r report  u undo  U redo  q quit".to_string(),
+        _ => ()
+    }
+
+    true
+}
+
+fn search_key(app: &mut App, code: KeyCode) -> bool
+{
+    match code
+    {
+        KeyCode::Esc | KeyCode::Tab => app.mode = Mode::Browse,
+        KeyCode::Down => {
+            let next = app.result_list.selected().map(|i| i + 1).unwrap_or(0).min(app.results.len().saturating_sub(1));
+            app.result_list.select(Some(next));
+        },
+        KeyCode::Up => {
+            let previous = app.result_list.selected().unwrap_or(0).saturating_sub(1);
+            app.result_list.select(Some(previous));
+        },
+        KeyCode::Enter => add_result(app),
+        KeyCode::Backspace => {
+            app.search_input.pop();
+            app.typed_at = Some(Instant::now());
+            local_search(app);
+        },
+        KeyCode::Char(character) => {
+            app.search_input.push(character);
+            app.typed_at = Some(Instant::now());
+            local_search(app);
+        },
+        _ => ()
+    }
+
+    true
+}
+
+fn quantity_key(app: &mut App, code: KeyCode) -> bool
+{
+    let typed = match &mut app.mode
+    {
+        Mode::Quantity(typed) => typed,
+        _ => return true
+    };
+
+    match code
+    {
+        KeyCode::Esc => app.mode = Mode::Browse,
+        KeyCode::Backspace => { typed.pop(); },
+        KeyCode::Char(digit) if digit.is_ascii_digit() => typed.push(digit),
+        KeyCode::Enter => {
+            let wanted = typed.parse::<usize>().ok();
+            app.mode = Mode::Browse;
+
+            match (wanted, selected(app).map(|holding| holding.indices.len()))
+            {
+                (Some(wanted), Some(current)) => change_quantity(app, wanted as isize - current as isize),
+                _ => app.status = "that's not a quantity".to_string()
+            }
+        },
+        _ => ()
+    }
+
+    true
+}
+
+fn confirm_key(app: &mut App, code: KeyCode) -> bool
+{
+    app.mode = Mode::Browse;
+
+    if code == KeyCode::Char('y')
+    {
+        let copies = selected(app).map(|holding| holding.indices.len()).unwrap_or(0);
+        change_quantity(app, -(copies as isize));
+    }
+
+    true
+}
+
+fn move_selection(app: &mut App, by: isize)
+{
+    if app.holdings.is_empty()
+    {
+        return;
+    }
+
+    let current = app.table.selected().unwrap_or(0) as isize;
+    let next = (current + by).clamp(0, app.holdings.len() as isize - 1);
+
+    app.table.select(Some(next as usize));
+}
+
+fn selected(app: &App) -> Option<&Holding>
+{
+    app.table.selected().and_then(|i| app.holdings.get(i))
+}
+
+fn product(app: &mut App, product_id: &str) -> Value
+{
+    if let Some(product_info) = app.products.get(product_id)
+    {
+        return product_info.clone();
+    }
+
+    let product_request = util::get_product_details(product_id, app.webcache.clone());
+    app.webcache = product_request.1;
+
+    let product_info: Value = serde_json::from_str(&product_request.0).unwrap_or_default();
+    app.products.insert(product_id.to_string(), product_info.clone());
+
+    product_info
+}
+
+// regroups the cards after any change, keeping the same holding selected when it's still there
+fn rebuild(app: &mut App)
+{
+    let selected_key = selected(app).map(|holding| (holding.product_id.clone(), holding.condition, holding.collection.clone(), holding.owner.clone()));
+
+    index::recount(&mut app.index, &app.db);
+
+    let mut groups: BTreeMap<(String, Condition, String, String), Vec<usize>> = BTreeMap::new();
+
+    for index in collection::view_indices(&app.db)
+    {
+        let card = &app.db.cards[index];
+        groups.entry((format_id(card.product_id.clone()), card.condition, card.collection.clone(), card.owner.clone())).or_default().push(index);
+    }
+
+    let mut holdings: Vec<Holding> = Vec::new();
+
+    for ((product_id, condition, collection, owner), indices) in groups
+    {
+        let product_info = product(app, &product_id);
+        let unit_price = valuation::unit_price(&product_info, app.settings.price_basis);
+
+        holdings.push(Holding {
+            name: util::display_name(&product_info),
+            set_name: util::set_name(&product_info),
+            value: unit_price.unwrap_or(0.0) * valuation::multiplier(condition, &app.settings),
+            unit_price,
+            product_id,
+            condition,
+            collection,
+            owner,
+            indices
+        });
+    }
+
+    app.holdings = holdings;
+    sort(app);
+
+    let position = selected_key.and_then(|key| app.holdings.iter().position(|holding| (holding.product_id.clone(), holding.condition, holding.collection.clone(), holding.owner.clone()) == key));
+
+    app.table.select(if app.holdings.is_empty() { None } else { Some(position.unwrap_or(0).min(app.holdings.len() - 1)) });
+}
+
+fn sort(app: &mut App)
+{
+    let column = app.sort;
+
+    app.holdings.sort_by(|a, b| {
+        let order = match column
+        {
+            SortColumn::Name => a.name.cmp(&b.name),
+            SortColumn::Set => a.set_name.cmp(&b.set_name),
+            SortColumn::Quantity => a.indices.len().cmp(&b.indices.len()),
+            SortColumn::Each => a.value.total_cmp(&b.value),
+            SortColumn::Total => (a.value * a.indices.len() as f64).total_cmp(&(b.value * b.indices.len() as f64))
+        };
+
+        order.then(a.name.cmp(&b.name)).then(a.collection.cmp(&b.collection))
+    });
+
+    if app.descending
+    {
+        app.holdings.reverse();
+    }
+}
+
+// saves, journals and regroups, putting the database back if it couldn't be saved
+fn commit(app: &mut App, before: Database, done: &str)
+{
+    if !util::save_db(app.db.clone(), &app.db_path)
+    {
+        app.db = before;
+        app.status = "[error] the database could not be saved!".to_string();
+        return;
+    }
+
+    journal::record(&mut app.history, &before, &app.db, &app.journal_path);
+
+    app.status = done.to_string();
+    rebuild(app);
+}
+
+// adds copies like the selected one, or removes them from the end into the archive
+fn change_quantity(app: &mut App, by: isize)
+{
+    let (indices, name) = match selected(app)
+    {
+        None => return,
+        Some(holding) => (holding.indices.clone(), holding.name.clone())
+    };
+
+    if by == 0
+    {
+        return;
+    }
+
+    let before = app.db.clone();
+
+    if by > 0
+    {
+        for _ in 0..by
+        {
+            app.db.cards.push(app.db.cards[indices[0]].clone());
+        }
+
+        commit(app, before, &format!("added {}x {}", by, name));
+        return;
+    }
+
+    let removing: Vec<usize> = indices.iter().rev().take(by.unsigned_abs()).cloned().collect();
+
+    // highest positions first so the rest don't shift
+    for index in &removing
+    {
+        let disposal = Disposal {
+            card: app.db.cards[*index].clone(),
+            name: name.clone(),
+            kind: DisposalKind::Removed,
+            date: chrono::Local::now().date_naive(),
+            sale_price: 0.0,
+            fees: 0.0,
+            shipping: 0.0,
+            counterparty: String::new(),
+            purchase_cost: None
+        };
+
+        ledger::archive(&mut app.db, *index, disposal);
+    }
+
+    commit(app, before, &format!("removed {}x {}", removing.len(), name));
+}
+
+fn restore(app: &mut App, restored: Option<Database>, action: &str)
+{
+    match restored
+    {
+        None => app.status = format!("nothing to {}", action),
+        Some(restored) => {
+            app.db = restored;
+
+            app.status = if util::save_db(app.db.clone(), &app.db_path) { format!("{} done", action) } else { "[error] the database could not be saved!".to_string() };
+            rebuild(app);
+        }
+    }
+}
+
+fn search(app: &mut App)
+{
+    let card_name = app.search_input.trim().to_string();
+
+    if card_name.is_empty()
+    {
+        return;
+    }
+
+    app.status = format!("searching for {}...", card_name);
+
+    let search_request = util::search(&card_name, app.webcache.clone());
+    app.webcache = search_request.1;
+
+    let response: Value = serde_json::from_str(&search_request.0).unwrap_or_default();
+
+    app.results = response["results"][0]["results"].as_array().cloned().unwrap_or_default().iter()
+        .filter(|product| product["productLineName"].as_str() == Some(util::PRODUCT_LINE) && !product["customAttributes"]["number"].as_str().unwrap_or_default().is_empty())
+        .filter_map(|product| Some(SearchResult {
+            product_id: product["productId"].as_number()?.clone(),
+            name: util::display_name(product),
+            set_name: util::set_name(product),
+            market_price: product["marketPrice"].as_f64()
+        }))
+        .collect();
+
+    app.result_list.select(if app.results.is_empty() { None } else { Some(0) });
+    app.status = format!("{} results", app.results.len());
+
+    index::add_search(&mut app.index, &search_request.0, &app.db);
+
+    // a typo tcgplayer can't make sense of can still fit something we've seen
+    if app.results.is_empty()
+    {
+        local_search(app);
+        app.status = format!("nothing on tcgplayer, {} cards you've seen", app.results.len());
+    }
+}
+
+// what we've already seen that fits the typing so far, until the real search replaces it
+fn local_search(app: &mut App)
+{
+    let matches = index::find(&app.index, &app.search_input);
+
+    app.results = matches.iter()
+        .filter_map(|found| {
+            let product_info: Value = serde_json::from_str(&util::check_cache(format!("get_product_details:{}", found.entry.product_id), &app.webcache).unwrap_or_default()).unwrap_or_default();
+
+            Some(SearchResult {
+                product_id: found.entry.product_id.parse::<u64>().ok()?.into(),
+                name: found.entry.name.clone(),
+                set_name: found.entry.set_name.clone(),
+                market_price: product_info["marketPrice"].as_f64()
+            })
+        })
+        .collect();
+
+    app.result_list.select(if app.results.is_empty() { None } else { Some(0) });
+    app.status = format!("{} cards you've seen, searching tcgplayer...", app.results.len());
+}
+
+fn add_result(app: &mut App)
+{
+    let result = match app.result_list.selected().and_then(|i| app.results.get(i))
+    {
+        None => return,
+        Some(result) => (result.product_id.clone(), result.name.clone())
+    };
+
+    let before = app.db.clone();
+
+    app.db.cards.push(Card { product_id: result.0, collection: collection::target(&app.db), owner: String::new(), condition: Condition::default() });
+
+    commit(app, before, &format!("added {}", result.1));
+}
+
+fn generate_report(app: &mut App)
+{
+    let generate_result = html::generate(collection::view(&app.db), app.webcache.clone(), &app.settings, html::report_path(&app.settings));
+    app.webcache = generate_result.1;
+
+    app.status = match generate_result.0
+    {
+        Ok(()) => format!("generated {}", html::report_path(&app.settings)),
+        Err(error) => format!("[error] {}", error)
+    };
+}
+
+fn draw(frame: &mut Frame, app: &mut App)
+{
+    let [header, body, footer] = Layout::vertical([Constraint::Length(1), Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+    let [table_area, side] = Layout::horizontal([Constraint::Percentage(62), Constraint::Percentage(38)]).areas(body);
+    let [search_area, detail_area] = Layout::vertical([Constraint::Percentage(45), Constraint::Percentage(55)]).areas(side);
+
+    let total: f64 = app.holdings.iter().map(|holding| holding.value * holding.indices.len() as f64).sum();
+    let cards: usize = app.holdings.iter().map(|holding| holding.indices.len()).sum();
+    let unknown: usize = app.holdings.iter().filter(|holding| holding.unit_price.is_none()).map(|holding| holding.indices.len()).sum();
+
+    let mut summary = format!(" {} | {} cards | {} by {}", collection::active_name(&app.db), cards, currency::format(&app.currency, total), valuation::basis_name(app.settings.price_basis));
+
+    if unknown > 0
+    {
+        summary.push_str(&format!(" | {} without a price", unknown));
+    }
+
+    if app.currency.date.is_some()
+    {
+        summary.push_str(&format!(" | {}", currency::rate_note(&app.currency)));
+    }
+
+    frame.render_widget(Paragraph::new(summary).style(Style::new().add_modifier(Modifier::BOLD)), header);
+
+    draw_table(frame, app, table_area);
+    draw_search(frame, app, search_area);
+    draw_detail(frame, app, detail_area);
+
+    let footer_text = match &app.mode
+    {
+        Mode::Quantity(typed) => format!(" new quantity: {}_  (enter to set, esc to cancel)", typed),
+        Mode::ConfirmRemove => format!(" remove all {} copies? (y/n)", selected(app).map(|holding| holding.indices.len()).unwrap_or(0)),
+        _ => format!(" {}", app.status)
+    };
+
+    frame.render_widget(Paragraph::new(footer_text), footer);
+}
+
+fn draw_table(frame: &mut Frame, app: &mut App, area: Rect)
+{
+    let arrow = if app.descending { "v" } else { "^" };
+    let titles = [("Card", SortColumn::Name), ("Set", SortColumn::Set), ("Qty", SortColumn::Quantity), ("Each", SortColumn::Each), ("Total", SortColumn::Total)];
+
+    let header = Row::new(titles.iter().map(|(title, column)| if *column == app.sort { format!("{} {}", title, arrow) } else { title.to_string() }))
+        .style(Style::new().add_modifier(Modifier::BOLD));
+
+    let rows: Vec<Row> = app.holdings.iter()
+        .map(|holding| {
+            let each = match holding.unit_price
+            {
+                None => "?".to_string(),
+                Some(_) => currency::format(&app.currency, holding.value)
+            };
+
+            Row::new(vec![
+                holding.name.clone(),
+                holding.set_name.clone(),
+                holding.indices.len().to_string(),
+                each,
+                currency::format(&app.currency, holding.value * holding.indices.len() as f64)
+            ])
+        })
+        .collect();
+
+    let widths = [Constraint::Fill(3), Constraint::Fill(2), Constraint::Length(4), Constraint::Length(10), Constraint::Length(11)];
+
+    let border_style = if app.mode == Mode::Search { Style::new() } else { Style::new().add_modifier(Modifier::BOLD) };
+
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(Block::bordered().title(" Collection ").border_style(border_style))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+
+    frame.render_stateful_widget(table, area, &mut app.table);
+}
+
+fn draw_search(frame: &mut Frame, app: &mut App, area: Rect)
+{
+    let [input_area, results_area] = Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
+
+    let searching = app.mode == Mode::Search;
+    let border_style = if searching { Style::new().add_modifier(Modifier::BOLD) } else { Style::new() };
+    let cursor = if searching { "_" } else { "" };
+
+    frame.render_widget(Paragraph::new(format!("{}{}", app.search_input, cursor)).block(Block::bordered().title(" Search (a) ").border_style(border_style)), input_area);
+
+    let items: Vec<ListItem> = app.results.iter()
+        .map(|result| {
+            let price = result.market_price.map(|price| currency::format(&app.currency, price)).unwrap_or("?".to_string());
+            ListItem::new(format!("{} - {} ({})", result.name, price, result.set_name))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::bordered().title(" Results (enter adds one) ").border_style(border_style))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+
+    frame.render_stateful_widget(list, results_area, &mut app.result_list);
+}
+
+// the search result or holding the details panel is about
+fn detail_product_id(app: &App) -> Option<String>
+{
+    if app.mode == Mode::Search
+    {
+        app.result_list.selected().and_then(|i| app.results.get(i)).map(|result| format_id(result.product_id.clone()))
+    }
+    else
+    {
+        selected(app).map(|holding| holding.product_id.clone())
+    }
+}
+
+// only shows what's already been fetched, the event loop fetches the rest between draws
+fn draw_detail(frame: &mut Frame, app: &App, area: Rect)
+{
+    let mut lines: Vec<Line> = Vec::new();
+
+    match detail_product_id(app).map(|product_id| app.products.get(&product_id))
+    {
+        None => (),
+        Some(None) => lines.push(Line::from("loading details...")),
+        Some(Some(product_info)) => detail_lines(app, product_info, &mut lines)
+    }
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: true }).block(Block::bordered().title(" Details ")), area);
+}
+
+fn detail_lines(app: &App, product_info: &Value, lines: &mut Vec<Line<'static>>)
+{
+    let price = valuation::unit_price(product_info, app.settings.price_basis).map(|price| currency::format(&app.currency, price)).unwrap_or("no price".to_string());
+
+    lines.push(Line::from(util::display_name(product_info)).style(Style::new().add_modifier(Modifier::BOLD)));
+    lines.push(Line::from(format!("Set: {}", util::set_name(product_info))));
+    lines.push(Line::from(format!("Rarity: {}", product_info["rarityName"].as_str().unwrap_or("?"))));
+    lines.push(Line::from(format!("Color: {}", util::attribute_text(&product_info["customAttributes"]["color"]))));
+    lines.push(Line::from(format!("Type: {}", util::attribute_text(&product_info["customAttributes"]["cardType"]))));
+    lines.push(Line::from(format!("Price ({}): {}", valuation::basis_name(app.settings.price_basis), price)));
+
+    if app.mode != Mode::Search
+    {
+        if let Some(holding) = selected(app)
+        {
+            lines.push(Line::from(format!("Condition: {}", valuation::condition_name(holding.condition))));
+            lines.push(Line::from(format!("Collection: {}", holding.collection)));
+            lines.push(Line::from(format!("Owner: {}", crate::trade::owner_name(&holding.owner))));
+        }
+    }
+
+    lines.push(Line::from(""));
+
+    for text in report::plain_text(product_info["customAttributes"]["description"].as_str().unwrap_or_default()).lines()
+    {
+        lines.push(Line::from(text.to_string()));
+    }
+}
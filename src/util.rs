@@ -1,80 +1,170 @@
 
-// util functions that make life easier
+//! util functions that make life easier
 
 // https://doc.rust-lang.org/book/ch12-02-reading-a-file.html
 // https://doc.rust-lang.org/book/ch09-02-recoverable-errors-with-result.html
 
-use std::{fs, collections::HashMap, time::UNIX_EPOCH};
+use std::{fs, collections::HashMap, io::Write};
 use base64::{prelude::BASE64_STANDARD, Engine};
 use serde_json::{Number, Value};
 use std::time::SystemTime;
 
+/// the collection cards go in when nothing else is picked
+pub const DEFAULT_COLLECTION: &str = "main";
+
+/// point this at another server (like `onepiececards mock`) to run without tcgplayer
+pub const API_URL_VAR: &str = "ONEPIECECARDS_API_URL";
+/// the same for card images
+pub const IMAGE_URL_VAR: &str = "ONEPIECECARDS_IMAGE_URL";
+
+/// tcgplayer's search and product details api
+pub const DEFAULT_API_URL: &str = "https://mp-search-api.tcgplayer.com";
+/// tcgplayer's image cdn
+pub const DEFAULT_IMAGE_URL: &str = "https://tcgplayer-cdn.tcgplayer.com";
+
+/// everything the program keeps, saved as cards.json
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
 pub struct Database
 {
-    pub cards: Vec<Card>
+    /// every card held, across every collection
+    pub cards: Vec<Card>,
+    /// collection names, in the order they were made
+    #[serde(default = "default_collections")]
+    pub collections: Vec<String>,
+    /// None means every collection at once
+    #[serde(default)]
+    pub active: Option<String>,
+    /// people cards can belong to
+    #[serde(default)]
+    pub owners: Vec<String>,
+    /// trades that went through, oldest first
+    #[serde(default)]
+    pub trades: Vec<crate::trade::Trade>,
+    /// cards that were sold, traded away or removed
+    #[serde(default)]
+    pub archive: Vec<crate::ledger::Disposal>,
+    /// booster boxes and packs opened, with what came out of them
+    #[serde(default)]
+    pub openings: Vec<crate::opening::Opening>
 }
 
+/// one physical card
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
 pub struct Card 
 {
-    pub product_id: Number
+    /// the printing's tcgplayer id
+    pub product_id: Number,
+    /// which collection it's in
+    #[serde(default = "default_collection")]
+    pub collection: String,
+    /// empty when nobody has claimed the card yet
+    #[serde(default)]
+    pub owner: String,
+    /// what shape it's in
+    #[serde(default)]
+    pub condition: crate::valuation::Condition
+}
+
+fn default_collection() -> String
+{
+    DEFAULT_COLLECTION.to_string()
+}
+
+fn default_collections() -> Vec<String>
+{
+    vec![default_collection()]
+}
+
+/// a database with nothing in it but the default collection
+pub fn empty_db() -> Database
+{
+    Database { cards: Vec::new(), collections: default_collections(), active: None, owners: Vec::new(), trades: Vec::new(), archive: Vec::new(), openings: Vec::new() }
 }
 
+/// the contents of a file, or the error message as text when it can't be read
 pub fn read_file(path: &str) -> String
 {
     let result = fs::read_to_string(path);
-    let content = match result {
+
+    match result {
         Ok(file) => file,
         Err(error) => error.to_string(),
-    };
-
-    return content;
+    }
 }
 
+/// writes next to the real file and renames it into place, so a crash never leaves half a file behind
 pub fn write_file(path: &str, data: &str) -> bool
 {
-    let op = fs::write(path, data);
-    let result = match op {
-        Ok(_) => true,
-        Err(_) => false
-    };
+    write_bytes(path, data.as_bytes())
+}
 
-    return result;
+/// write_file for images and anything else that isn't text
+pub fn write_bytes(path: &str, data: &[u8]) -> bool
+{
+    let temp_path = format!("{}.tmp", path);
+
+    let op = fs::File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(data)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+
+    if op.is_err()
+    {
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    op.is_ok()
 }
 
+/// loads the database, an empty one when it can't be read
+///
+/// saving that empty one replaces whatever was there, use load to tell a broken database from an empty one
 pub fn import(path: &str) -> Database {
-    let data = read_file(path);
-    let db_result = serde_json::from_str(data.as_str());
-
-    let db = match db_result {
-        Ok(db) => db,
-        Err(error) => {
-            println!("[!!] Could not import database!\n[!!] {}", error.to_string());
-            println!("[!!] Using empty database... This will overwrite your current one if you have it saved!");
-            pause_console::pause_console!();
-            Database { cards: Vec::new() }
+    load(path).unwrap_or_else(|_| empty_db())
+}
+
+/// loads the database, or says why it can't be read or doesn't parse
+pub fn load(path: &str) -> Result<Database, String>
+{
+    let data = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let mut db: Database = serde_json::from_str(&data).map_err(|error| error.to_string())?;
+
+    // older databases only know about the cards, so make sure every collection they use is listed
+    for card in &db.cards
+    {
+        if !db.collections.contains(&card.collection)
+        {
+            db.collections.push(card.collection.clone());
         }
-    };
+    }
 
-    return db;
+    Ok(db)
 }
 
+/// backs up the old database (at most every half hour) and writes the new one in its place
 pub fn save_db(db: Database, db_path: &str) -> bool
 {
     let json = serde_json::to_string(&db).unwrap();
-    return write_file(db_path, &json);
+
+    // the backup is a nice to have, a failed one shouldn't stop the save
+    crate::backup::rotate(db_path);
+
+    write_file(db_path, &json)
 }
 
+/// empties the web cache on disk and returns the empty cache
 pub fn clear_cache(cache_path: &str) -> HashMap<String, String>
 {
     let new_webcache: HashMap<String, String> = HashMap::new();
 
-    let _ = save_cache(new_webcache.clone(), &cache_path);
+    let _ = save_cache(new_webcache.clone(), cache_path);
 
-    return new_webcache;
+    new_webcache
 }
 
+/// writes the web cache, every tcgplayer response keyed by "method:argument"
 pub fn save_cache(webcache: HashMap<String, String>, cache_path: &str) -> bool 
 {
     let json_request = serde_json::to_string(&webcache);
@@ -86,100 +176,286 @@ pub fn save_cache(webcache: HashMap<String, String>, cache_path: &str) -> bool
     }
 }
 
+/// loads the web cache, empty when there isn't one
 pub fn import_cache(cache_path: &str) -> HashMap<String, String> {
     let content = read_file(cache_path);
 
     let json_request: Result<HashMap<String, String>, serde_json::Error> = serde_json::from_str(&content);
 
-    match json_request
-    {
-        Ok(webcache) => return webcache,
-        Err(_) => return HashMap::new()
-    }
+    json_request.unwrap_or_default()
 }
 
+/// how many hours ago a file was last written, u64::MAX when it doesn't exist
+///
+/// a file written in the future (a skewed clock or a config folder copied from another computer) can't say how old it is, so it counts as older than any limit
 pub fn cache_old(path: &str) -> u64 {
 
-    let mut file_since_epoch: u64 = u64::MAX;
-
-    match fs::metadata(path)
+    let modified = match fs::metadata(path).and_then(|file| file.modified())
     {
-        Ok(file) => file_since_epoch = file.modified().unwrap().duration_since(UNIX_EPOCH).unwrap().as_secs(),
-        Err(_) => ()
+        Ok(modified) => modified,
+        Err(_) => return u64::MAX
     };
 
-    let start = SystemTime::now();
-    
-    let current_since_epoch = start
-    .duration_since(UNIX_EPOCH).unwrap().as_secs();
-
-    if file_since_epoch == u64::MAX 
+    match SystemTime::now().duration_since(modified)
     {
-        return file_since_epoch;
+        Ok(age) => age.as_secs() / 60 / 60,
+        Err(_) => u64::MAX - 1
     }
-
-    return (current_since_epoch / 60 / 60) - (file_since_epoch / 60 / 60)
 }
 
+/// true when the cache is more than a day old
 pub fn cache_too_old(cache_old: u64) -> bool
 {
     cache_old > 24
 }
 
-pub fn clear(webcache: HashMap<String, String>)
-{
-    print!("{esc}c", esc = 27 as char);
-    println!("###################");
-    println!("#     card db     #");
-    println!("###################\n");
-    println!("Cache: {} requests\n", webcache.len());
-}
-
+/// tcgplayer product ids as they're used in urls and cache keys, eg. 1001 rather than 1001.0
 pub fn format_id(id: Number) -> String
 {
     format!("{}", id).replace(".0", "")
 }
 
-pub fn pause()
+/// "0, 2,3" -> [0, 2, 3], None if anything in the list isn't a number
+pub fn parse_indices(input: &str) -> Option<Vec<usize>>
 {
-    pause_console::pause_console!("");
-    pause_console::pause_console!();
+    let mut indices: Vec<usize> = Vec::new();
+
+    for part in input.split(',')
+    {
+        let part = part.trim();
+
+        if part.is_empty()
+        {
+            continue;
+        }
+
+        match part.parse::<usize>()
+        {
+            Ok(index) => indices.push(index),
+            Err(_) => return None
+        }
+    }
+
+    Some(indices)
 }
 
+/// the cached response for a request, None when it was never made
 pub fn check_cache(method_params: String, webcache: &HashMap<String, String>) -> Option<String>
 {
     let result = webcache.get(&method_params);
-    return result.cloned();
+    result.cloned()
+}
+
+/// where search and product details come from, without a trailing slash
+pub fn api_url() -> String
+{
+    std::env::var(API_URL_VAR).unwrap_or(DEFAULT_API_URL.to_string()).trim_end_matches('/').to_string()
 }
 
-pub fn get_product_details(formatted_product_id: &str, mut webcache: HashMap<String, String>) -> (String, HashMap<String, String>)
+/// where card pictures come from, without a trailing slash
+pub fn image_url() -> String
+{
+    std::env::var(IMAGE_URL_VAR).unwrap_or(DEFAULT_IMAGE_URL.to_string()).trim_end_matches('/').to_string()
+}
+
+// failed requests aren't cached, so they get tried again next time
+fn unreachable(error: reqwest::Error) -> String
+{
+    format!("could not reach tcgplayer! {}", error)
+}
+
+/// tcgplayer's product details json for one product, from the cache when it's there, empty when it can't be fetched
+pub fn get_product_details(formatted_product_id: &str, webcache: HashMap<String, String>) -> (String, HashMap<String, String>)
+{
+    let (body, webcache) = try_get_product_details(formatted_product_id, webcache);
+    (body.unwrap_or_default(), webcache)
+}
+
+/// like get_product_details, with why it couldn't be fetched
+pub fn try_get_product_details(formatted_product_id: &str, mut webcache: HashMap<String, String>) -> (Result<String, String>, HashMap<String, String>)
 {
     let cache_result = check_cache(format!("get_product_details:{}", formatted_product_id), &webcache);
 
-    if cache_result != None
+    if let Some(cached) = cache_result
     {
-        return (cache_result.unwrap(), webcache);
+        return (Ok(cached), webcache);
     }
 
     let client = reqwest::blocking::Client::builder().build().unwrap();
 
-    let request = client.request(reqwest::Method::GET, format!("https://mp-search-api.tcgplayer.com/v2/product/{}/details", formatted_product_id));
+    let request = client.request(reqwest::Method::GET, format!("{}/v2/product/{}/details", api_url(), formatted_product_id));
 
-    let response = request.send().unwrap();
-    let body = response.text().unwrap();
+    let body = match request.send().and_then(|response| response.error_for_status()).and_then(|response| response.text())
+    {
+        Ok(body) => body,
+        Err(error) => return (Err(unreachable(error)), webcache)
+    };
 
     webcache.insert(format!("get_product_details:{}", formatted_product_id), body.clone());
 
-    return (format!("{}", body), webcache);
+    (Ok(body), webcache)
 }
 
-pub fn search(card_name: &str, mut webcache: HashMap<String, String>) -> (String, HashMap<String, String>)
+/// how many results one page of a search holds
+pub const SEARCH_PAGE_SIZE: usize = 24;
+
+/// searches only ever look at this product line
+pub const PRODUCT_LINE: &str = "One Piece Card Game";
+
+/// what a search can be narrowed down by on top of the name, None is anything
+#[derive(Clone, Default, PartialEq)]
+pub struct SearchFilters
 {
-    let cache_result = check_cache(format!("search:{}", card_name), &webcache);
+    /// eg. "Romance Dawn" or "romance-dawn"
+    pub set: Option<String>,
+    /// eg. "Super Rare"
+    pub rarity: Option<String>,
+    /// eg. "Red"
+    pub color: Option<String>,
+    /// eg. "Leader" or "Character"
+    pub card_type: Option<String>,
+    /// market price in dollars
+    pub min_price: Option<f64>,
+    /// market price in dollars
+    pub max_price: Option<f64>
+}
 
-    if cache_result != None
+/// the names search_filter takes, eg. set=romance-dawn
+pub const SEARCH_FILTER_NAMES: [&str; 6] = ["set", "rarity", "color", "type", "min", "max"];
+
+/// "Romance Dawn" -> "romance-dawn", how tcgplayer writes names in its filters
+pub fn url_name(name: &str) -> String
+{
+    name.trim().to_lowercase().split(|character: char| !character.is_alphanumeric()).filter(|word| !word.is_empty()).collect::<Vec<&str>>().join("-")
+}
+
+/// sets one filter from a name in SEARCH_FILTER_NAMES and its value, a blank value clears it
+///
+/// false when the name isn't a filter or a price isn't a number
+pub fn search_filter(filters: &mut SearchFilters, name: &str, value: &str) -> bool
+{
+    let value = value.trim();
+    let text = (!value.is_empty()).then(|| value.to_string());
+
+    match name
     {
-        return (cache_result.unwrap(), webcache);
+        "set" => filters.set = text,
+        "rarity" => filters.rarity = text,
+        "color" => filters.color = text,
+        "type" => filters.card_type = text,
+        "min" | "max" => {
+            let price = match value.trim_start_matches('$').parse::<f64>()
+            {
+                _ if value.is_empty() => None,
+                Ok(price) if price >= 0.0 => Some(price),
+                _ => return false
+            };
+
+            if name == "min"
+            {
+                filters.min_price = price;
+            }
+            else
+            {
+                filters.max_price = price;
+            }
+        },
+        _ => return false
+    }
+
+    true
+}
+
+/// eg. "set romance-dawn, rarity Super Rare, at least $1.00", empty when nothing is filtered
+pub fn describe_filters(filters: &SearchFilters) -> String
+{
+    let mut parts: Vec<String> = Vec::new();
+
+    for (name, value) in [("set", &filters.set), ("rarity", &filters.rarity), ("color", &filters.color), ("type", &filters.card_type)]
+    {
+        if let Some(value) = value
+        {
+            parts.push(format!("{} {}", name, value));
+        }
+    }
+
+    if let Some(min_price) = filters.min_price
+    {
+        parts.push(format!("at least ${:.2}", min_price));
+    }
+
+    if let Some(max_price) = filters.max_price
+    {
+        parts.push(format!("at most ${:.2}", max_price));
+    }
+
+    parts.join(", ")
+}
+
+// the filters part of the search body, in tcgplayer's layout, which wants url names, eg. "super-rare" for "Super Rare"
+fn filter_json(filters: &SearchFilters) -> Value
+{
+    let mut term = serde_json::json!({ "productLineName": [url_name(PRODUCT_LINE)] });
+
+    for (field, value) in [("setName", &filters.set), ("rarityName", &filters.rarity), ("color", &filters.color), ("cardType", &filters.card_type)]
+    {
+        if let Some(value) = value
+        {
+            term[field] = serde_json::json!([url_name(value)]);
+        }
+    }
+
+    let mut price = serde_json::Map::new();
+
+    if let Some(min_price) = filters.min_price
+    {
+        price.insert("gte".to_string(), min_price.into());
+    }
+
+    if let Some(max_price) = filters.max_price
+    {
+        price.insert("lte".to_string(), max_price.into());
+    }
+
+    let range = if price.is_empty() { serde_json::json!({}) } else { serde_json::json!({ "marketPrice": price }) };
+
+    serde_json::json!({ "term": term, "range": range, "match": {} })
+}
+
+/// tcgplayer's search results json for the first page of a card name, from the cache when it's there
+pub fn search(card_name: &str, webcache: HashMap<String, String>) -> (String, HashMap<String, String>)
+{
+    search_page(card_name, 0, &SearchFilters::default(), webcache)
+}
+
+/// one page of tcgplayer's search results json, pages count from 0, empty when it can't be fetched
+pub fn search_page(card_name: &str, page: usize, filters: &SearchFilters, webcache: HashMap<String, String>) -> (String, HashMap<String, String>)
+{
+    let (body, webcache) = try_search_page(card_name, page, filters, webcache);
+    (body.unwrap_or_default(), webcache)
+}
+
+/// like search_page, with why it couldn't be fetched
+pub fn try_search_page(card_name: &str, page: usize, filters: &SearchFilters, mut webcache: HashMap<String, String>) -> (Result<String, String>, HashMap<String, String>)
+{
+    // the first unfiltered page keeps the key it always had so older caches still work
+    let mut cache_key = format!("search:{}", card_name);
+
+    if page > 0
+    {
+        cache_key.push_str(&format!(":page{}", page));
+    }
+
+    if *filters != SearchFilters::default()
+    {
+        cache_key.push_str(&format!(":{}", filter_json(filters)));
+    }
+
+    let cache_result = check_cache(cache_key.clone(), &webcache);
+
+    if let Some(cached) = cache_result
+    {
+        return (Ok(cached), webcache);
     }
 
     let client = reqwest::blocking::Client::builder()
@@ -190,48 +466,104 @@ pub fn search(card_name: &str, mut webcache: HashMap<String, String>) -> (String
 
     let data = r#"{"algorithm":"sales_synonym_v2","from":0,"size":24,"filters":{"term":{},"range":{},"match":{}},"listingSearch":{"context":{"cart":{}},"filters":{"term":{"sellerStatus":"Live","channelId":0},"range":{"quantity":{"gte":1}},"exclude":{"channelExclusion":0}}},"context":{"cart":{},"shippingCountry":"US","userProfile":{}},"settings":{"useFuzzySearch":true,"didYouMean":{}},"sort":{}}"#;
 
-    let json: serde_json::Value = serde_json::from_str(&data).unwrap();
+    let mut json: serde_json::Value = serde_json::from_str(data).unwrap();
+    json["from"] = (page * SEARCH_PAGE_SIZE).into();
+    json["size"] = SEARCH_PAGE_SIZE.into();
+    json["filters"] = filter_json(filters);
 
+    // .query encodes the name, so & # and non-ascii names make it through
     let request = client.request(reqwest::Method::POST, 
-        format!("https://mp-search-api.tcgplayer.com/v1/search/request?q={}", card_name))
+        format!("{}/v1/search/request", api_url()))
+        .query(&[("q", card_name)])
         .headers(headers)
         .json(&json);
 
-    let response = request.send().unwrap();
-    let body = response.text().unwrap();
+    let body = match request.send().and_then(|response| response.error_for_status()).and_then(|response| response.text())
+    {
+        Ok(body) => body,
+        Err(error) => return (Err(unreachable(error)), webcache)
+    };
 
-    webcache.insert(format!("search:{}", card_name), body.clone());
+    webcache.insert(cache_key, body.clone());
 
-    return (format!("{}", body), webcache);
+    (Ok(body), webcache)
 }
 
-pub fn card_image_b64(formatted_product_id: &str, mut webcache: HashMap<String, String>) -> (String, HashMap<String, String>)
+/// how many pages a search has in total, from any page of its results json
+pub fn search_pages(response: &Value) -> usize
+{
+    let total = response["results"][0]["totalResults"].as_u64().unwrap_or_default() as usize;
+
+    total.div_ceil(SEARCH_PAGE_SIZE)
+}
+
+/// a card's picture as base64 jpeg, from the cache when it's there, empty when it can't be fetched
+pub fn card_image_b64(formatted_product_id: &str, webcache: HashMap<String, String>) -> (String, HashMap<String, String>)
+{
+    let (image, webcache) = try_card_image_b64(formatted_product_id, webcache);
+    (image.unwrap_or_default(), webcache)
+}
+
+/// like card_image_b64, with why it couldn't be fetched
+pub fn try_card_image_b64(formatted_product_id: &str, mut webcache: HashMap<String, String>) -> (Result<String, String>, HashMap<String, String>)
 {
     let cache_result = check_cache(format!("card_image_b64:{}", formatted_product_id), &webcache);
 
-    if cache_result != None
+    if let Some(cached) = cache_result
     {
-        return (cache_result.unwrap(), webcache);
+        return (Ok(cached), webcache);
     }
 
     let client = reqwest::blocking::Client::builder().build().unwrap();
 
-    let request = client.request(reqwest::Method::GET, format!("https://tcgplayer-cdn.tcgplayer.com/product/{}_in_1000x1000.jpg", formatted_product_id));
+    let request = client.request(reqwest::Method::GET, format!("{}/product/{}_in_1000x1000.jpg", image_url(), formatted_product_id));
 
-    let response = request.send().unwrap();
-    let bytes = response.bytes().unwrap();
+    let bytes = match request.send().and_then(|response| response.error_for_status()).and_then(|response| response.bytes())
+    {
+        Ok(bytes) => bytes,
+        Err(error) => return (Err(unreachable(error)), webcache)
+    };
 
     webcache.insert(format!("card_image_b64:{}", formatted_product_id), BASE64_STANDARD.encode(bytes.clone()));
     
-    return (BASE64_STANDARD.encode(bytes), webcache);
-}
-
-pub fn total_worth(db: &Database, webcache: HashMap<String, String>) -> (f64, HashMap<String, String>) {
-    db.cards.iter().fold((0.0, webcache.clone()), |acc: (f64, HashMap<String, String>), product| {
-        let product_info: Value = serde_json::from_str(
-            &get_product_details(&format_id(product.product_id.clone()), webcache.clone()).0
-        ).unwrap();
-        
-        (acc.0 + product_info["marketPrice"].as_f64().unwrap_or(0.0), acc.1)
-    })
-}
\ No newline at end of file
+    (Ok(BASE64_STANDARD.encode(bytes)), webcache)
+}
+
+/// the name shown everywhere, eg. "Nami OP01-016" with "(Pre Release)" on pre release cards
+pub fn display_name(product_info: &Value) -> String
+{
+    let product_name = product_info["productName"].as_str().unwrap_or_default();
+    let set_url_name = product_info["setUrlName"].as_str().unwrap_or_default();
+    let op_code = product_info["customAttributes"]["number"].as_str().unwrap_or_default();
+
+    if set_url_name.contains("Pre Release")
+    {
+        format!("{} {} (Pre Release)", product_name, op_code)
+    }
+    else
+    {
+        format!("{} {}", product_name, op_code)
+    }
+}
+
+/// the set a product belongs to, as tcgplayer names it
+pub fn set_name(product_info: &Value) -> String
+{
+    match product_info["setUrlName"].as_str().or(product_info["setName"].as_str())
+    {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => "Unknown Set".to_string()
+    }
+}
+
+/// tcgplayer sends some custom attributes as a list and others as a plain value, eg. ["Red", "Green"] -> "Red/Green"
+pub fn attribute_text(attribute: &Value) -> String
+{
+    match attribute
+    {
+        Value::Array(values) => values.iter().map(attribute_text).filter(|text| !text.is_empty()).collect::<Vec<String>>().join("/"),
+        Value::String(text) => text.trim().to_string(),
+        Value::Number(number) => number.to_string(),
+        _ => String::new()
+    }
+}
@@ -0,0 +1,219 @@
+//! what the collection is worth, card by card and set by set, at whichever price the user trusts
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::Value;
+
+use crate::settings::Settings;
+use crate::util::{self, format_id, Database};
+
+/// which tcgplayer price a card is valued at
+///
+/// there's no mid price, tcgplayer's product details only have the market price, lowest listing and median listing
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum PriceBasis
+{
+    /// what copies have actually been selling for
+    #[default]
+    Market,
+    /// the cheapest listing
+    Low,
+    /// the middle of the current listings
+    Median
+}
+
+/// every basis, in the order the settings menu cycles through them
+pub const PRICE_BASES: [PriceBasis; 3] = [PriceBasis::Market, PriceBasis::Low, PriceBasis::Median];
+
+/// what shape a copy is in, using tcgplayer's grades
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Condition
+{
+    /// straight out of the pack
+    #[default]
+    NearMint,
+    /// minor edge or corner wear
+    LightlyPlayed,
+    /// wear you notice at a glance
+    ModeratelyPlayed,
+    /// heavy wear, still playable in a sleeve
+    HeavilyPlayed,
+    /// creased, torn, inked or water damaged
+    Damaged
+}
+
+/// every condition, best first
+pub const CONDITIONS: [Condition; 5] = [Condition::NearMint, Condition::LightlyPlayed, Condition::ModeratelyPlayed, Condition::HeavilyPlayed, Condition::Damaged];
+
+/// one card's value
+#[derive(serde::Serialize, Clone)]
+pub struct CardValue
+{
+    /// position in db.cards
+    pub index: usize,
+    /// the printing's tcgplayer id, formatted
+    pub product_id: String,
+    /// with the op code and pre release tag, what the menu shows
+    pub name: String,
+    /// the set it was printed in
+    pub set_name: String,
+    /// what shape the copy is in
+    pub condition: Condition,
+    /// None when tcgplayer has no price of this kind for the card
+    pub unit_price: Option<f64>,
+    /// the unit price after the condition multiplier, 0 when the price is unknown
+    pub value: f64
+}
+
+/// one set's cards added together
+#[derive(serde::Serialize, Clone)]
+pub struct SetValue
+{
+    /// the set's name
+    pub name: String,
+    /// how many cards are in it
+    pub count: u32,
+    /// how many of them have no price
+    pub unknown: u32,
+    /// what they're worth together
+    pub value: f64
+}
+
+/// what the collection is worth and how that breaks down
+#[derive(serde::Serialize, Clone)]
+pub struct Valuation
+{
+    /// which price the values are based on
+    pub basis: PriceBasis,
+    /// what every card is worth together, in dollars
+    pub total: f64,
+    /// how many cards were valued
+    pub card_count: u32,
+    /// cards that count as nothing because they have no price
+    pub unknown: u32,
+    /// every card, in database order
+    pub cards: Vec<CardValue>,
+    /// value by set, by set name
+    pub sets: Vec<SetValue>
+}
+
+/// eg. "market price", as the settings menu shows it
+pub fn basis_name(basis: PriceBasis) -> &'static str
+{
+    match basis
+    {
+        PriceBasis::Market => "market price",
+        PriceBasis::Low => "lowest listing",
+        PriceBasis::Median => "median listing"
+    }
+}
+
+fn price_field(basis: PriceBasis) -> &'static str
+{
+    match basis
+    {
+        PriceBasis::Market => "marketPrice",
+        PriceBasis::Low => "lowestPrice",
+        PriceBasis::Median => "medianPrice"
+    }
+}
+
+/// eg. "near mint", as the card editor shows it
+pub fn condition_name(condition: Condition) -> &'static str
+{
+    match condition
+    {
+        Condition::NearMint => "near mint",
+        Condition::LightlyPlayed => "lightly played",
+        Condition::ModeratelyPlayed => "moderately played",
+        Condition::HeavilyPlayed => "heavily played",
+        Condition::Damaged => "damaged"
+    }
+}
+
+// roughly how tcgplayer sellers discount played cards
+fn default_multiplier(condition: Condition) -> f64
+{
+    match condition
+    {
+        Condition::NearMint => 1.0,
+        Condition::LightlyPlayed => 0.85,
+        Condition::ModeratelyPlayed => 0.7,
+        Condition::HeavilyPlayed => 0.5,
+        Condition::Damaged => 0.3
+    }
+}
+
+/// what a card in this condition is worth compared to near mint, from settings or the defaults
+pub fn multiplier(condition: Condition, settings: &Settings) -> f64
+{
+    *settings.condition_multipliers.get(&condition).unwrap_or(&default_multiplier(condition))
+}
+
+/// one copy's price at the chosen basis from product details json, None when tcgplayer has none
+pub fn unit_price(product_info: &Value, basis: PriceBasis) -> Option<f64>
+{
+    product_info[price_field(basis)].as_f64()
+}
+
+/// one copy of a product at the chosen basis, before any condition multiplier
+pub fn price(formatted_product_id: &str, basis: PriceBasis, webcache: HashMap<String, String>) -> (Option<f64>, HashMap<String, String>)
+{
+    let product_request = util::get_product_details(formatted_product_id, webcache);
+    let product_info: Value = serde_json::from_str(&product_request.0).unwrap_or_default();
+
+    (unit_price(&product_info, basis), product_request.1)
+}
+
+/// values every card in the database, cards without a price count as nothing and are counted in `unknown`
+pub fn value(db: &Database, mut webcache: HashMap<String, String>, settings: &Settings) -> (Valuation, HashMap<String, String>)
+{
+    let mut cards: Vec<CardValue> = Vec::new();
+
+    for (index, card) in db.cards.iter().enumerate()
+    {
+        let product_id = format_id(card.product_id.clone());
+
+        let product_request = util::get_product_details(&product_id, webcache);
+        webcache = product_request.1;
+
+        let product_info: Value = serde_json::from_str(&product_request.0).unwrap_or_default();
+        let unit_price = unit_price(&product_info, settings.price_basis);
+
+        cards.push(CardValue {
+            index,
+            product_id,
+            name: util::display_name(&product_info),
+            set_name: util::set_name(&product_info),
+            condition: card.condition,
+            unit_price,
+            value: unit_price.unwrap_or(0.0) * multiplier(card.condition, settings)
+        });
+    }
+
+    let mut sets: BTreeMap<String, SetValue> = BTreeMap::new();
+
+    for card in &cards
+    {
+        let set = sets.entry(card.set_name.clone()).or_insert(SetValue { name: card.set_name.clone(), count: 0, unknown: 0, value: 0.0 });
+
+        set.count += 1;
+        set.value += card.value;
+
+        if card.unit_price.is_none()
+        {
+            set.unknown += 1;
+        }
+    }
+
+    let valuation = Valuation {
+        basis: settings.price_basis,
+        total: cards.iter().map(|card| card.value).sum(),
+        card_count: cards.len() as u32,
+        unknown: cards.iter().filter(|card| card.unit_price.is_none()).count() as u32,
+        cards,
+        sets: sets.into_values().collect()
+    };
+
+    (valuation, webcache)
+}
@@ -0,0 +1,67 @@
+//! the database file: loading it, and the backups made when saving and restoring
+
+mod common;
+
+use onepiececards::backup;
+use onepiececards::util;
+
+#[test]
+fn restoring_the_oldest_backup_keeps_it()
+{
+    let folder = common::scratch("backups");
+    let db_path = folder.join("cards.json").to_string_lossy().to_string();
+    let backups = folder.join("backups");
+
+    std::fs::create_dir_all(&backups).unwrap();
+    std::fs::write(&db_path, serde_json::to_string(&common::db(&[1001])).unwrap()).unwrap();
+
+    // a full set of backups, the oldest with two cards
+    for day in 1..=10
+    {
+        let product_ids: &[u64] = if day == 1 { &[1002, 1003] } else { &[1001] };
+        std::fs::write(backups.join(format!("cards-202401{:02}-120000-000.json", day)), serde_json::to_string(&common::db(product_ids)).unwrap()).unwrap();
+    }
+
+    let oldest = backup::list(&db_path).pop().unwrap();
+    let restored = backup::restore(&db_path, &oldest).expect("the backup should be restored");
+
+    assert_eq!(restored.cards.len(), 2);
+    assert!(oldest.path.exists(), "the restored backup shouldn't be pruned");
+    assert_eq!(util::import(&db_path).cards.len(), 2);
+    assert_eq!(backup::list(&db_path).len(), 11);
+}
+
+#[test]
+fn saves_close_together_share_a_backup()
+{
+    let folder = common::scratch("backups");
+    let db_path = folder.join("cards.json").to_string_lossy().to_string();
+
+    std::fs::write(&db_path, serde_json::to_string(&common::db(&[1001])).unwrap()).unwrap();
+
+    assert!(util::save_db(common::db(&[1001, 1002]), &db_path));
+    assert!(util::save_db(common::db(&[1001, 1002, 1003]), &db_path));
+
+    let backups = backup::list(&db_path);
+
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backup::load(&backups[0]).unwrap().cards.len(), 1, "the backup should be from before the session");
+}
+
+#[test]
+fn broken_databases_are_told_apart_from_empty_ones()
+{
+    let folder = common::scratch("backups");
+    let db_path = folder.join("cards.json").to_string_lossy().to_string();
+
+    assert!(util::load(&db_path).is_err(), "a missing database should be an error");
+
+    std::fs::write(&db_path, "{\"cards\": [").unwrap();
+
+    assert!(util::load(&db_path).is_err());
+    assert!(util::import(&db_path).cards.is_empty(), "import should fall back without asking anything");
+
+    std::fs::write(&db_path, serde_json::to_string(&common::db(&[1001])).unwrap()).unwrap();
+
+    assert_eq!(util::load(&db_path).unwrap().cards.len(), 1);
+}
@@ -0,0 +1,411 @@
+//! the program itself, run against the mock from a scratch folder with its input piped in
+
+mod common;
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use onepiececards::{settings, util};
+
+fn run(folder: &Path, args: &[&str], input: &str) -> Output
+{
+    let mock_url = common::mock();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_onepiececards"))
+        .args(args)
+        .current_dir(folder)
+        .env(util::API_URL_VAR, mock_url)
+        .env(util::IMAGE_URL_VAR, mock_url)
+        .env(settings::CONFIG_DIR_VAR, folder.join("config"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the program should start");
+
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+// a scratch folder with a database in it
+fn collection(product_ids: &[u64]) -> PathBuf
+{
+    let folder = common::scratch("cli");
+    std::fs::write(folder.join("cards.json"), serde_json::to_string(&common::db(product_ids)).unwrap()).unwrap();
+    folder
+}
+
+#[test]
+fn adding_a_card_from_search()
+{
+    let folder = collection(&[]);
+
+    // add a card, search luffy, take 2 of result 0 in the default condition, then quit
+    // the name prompt wants an empty line before the name
+    let output = run(&folder, &["classic"], "1\n\nluffy\n0:2\n\n14\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("[0] Monkey.D.Luffy OP01-024"));
+    assert!(!stdout.contains("Luffy Sleeves"), "only one piece cards are offered");
+
+    let db = util::import(&folder.join("cards.json").to_string_lossy());
+    assert_eq!(db.cards.len(), 2);
+    assert_eq!(util::format_id(db.cards[0].product_id.clone()), "1001");
+
+    // quitting saves what was looked up
+    let webcache = util::import_cache(&folder.join("webcache.dat").to_string_lossy());
+    assert!(webcache.contains_key("search:luffy"));
+    assert!(webcache.contains_key("get_product_details:1001"));
+}
+
+#[test]
+fn the_menu_opens_without_a_command()
+{
+    let folder = collection(&[1001]);
+
+    // no database questions with one in place, then quit
+    let output = run(&folder, &[], "14\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("Collection: all collections"), "{}", stdout);
+
+    // the full screen view is asked for, and needs a terminal
+    let output = run(&folder, &["tui"], "");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("needs a terminal"));
+}
+
+#[test]
+fn adding_a_card_from_the_next_page()
+{
+    let folder = collection(&[]);
+
+    // next page, back, next again, then take 1 of the only card there
+    let output = run(&folder, &["classic"], "1\n\nluffy\nn\np\nn\n0:1\n\n14\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("Page 2 of 2"));
+    assert!(stdout.contains("[n] next page"));
+    assert!(stdout.contains("[p] previous page"));
+
+    let db = util::import(&folder.join("cards.json").to_string_lossy());
+    assert_eq!(db.cards.len(), 1);
+    assert_eq!(util::format_id(db.cards[0].product_id.clone()), "1004");
+}
+
+#[test]
+fn adding_a_card_with_filters()
+{
+    let folder = collection(&[]);
+
+    // filter to leaders up to $5, leaving set, rarity, color and the lowest price alone
+    let output = run(&folder, &["classic"], "1\n\nstraw hat\nf\n\n\n\nLeader\n\n5\n0:1\n\n14\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("Filters: type Leader, at most $5.00"));
+
+    let db = util::import(&folder.join("cards.json").to_string_lossy());
+    assert_eq!(db.cards.len(), 1);
+    assert_eq!(util::format_id(db.cards[0].product_id.clone()), "1004");
+}
+
+#[test]
+fn bad_selections_add_nothing()
+{
+    let folder = collection(&[]);
+
+    // a selection without a count, then one past the end of the results
+    let output = run(&folder, &["classic"], "1\n\nluffy\n0\n\n1\n\nluffy\n7:1\n\n14\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("Incorrect Count Format!"));
+    assert!(stdout.contains("No card at that position!"));
+    assert!(!folder.join("backups").exists(), "nothing should have been saved");
+}
+
+#[test]
+fn searches_with_no_results_add_nothing()
+{
+    let folder = collection(&[]);
+
+    let output = run(&folder, &["classic"], "1\n\nnobody\n\n14\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("No results!"));
+}
+
+#[test]
+fn markdown_report_command()
+{
+    let folder = collection(&[1001, 1002, 1003]);
+
+    let output = run(&folder, &["report", "markdown"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("Total Worth: $15.75 (3 cards, by market price)"));
+    assert!(util::import_cache(&folder.join("webcache.dat").to_string_lossy()).contains_key("get_product_details:1003"));
+}
+
+#[test]
+fn html_report_command()
+{
+    let folder = collection(&[1001]);
+
+    let output = run(&folder, &["report", "html"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("generated report.html"), "{}", stdout);
+
+    let page = std::fs::read_to_string(folder.join("report.html")).unwrap();
+    assert!(page.contains("Monkey.D.Luffy"));
+    assert!(page.contains("data:image/jpeg;base64,"));
+
+    // the file comes before the query like it does for the other formats
+    let output = run(&folder, &["report", "binder", "sheets.html", "color=red"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("generated sheets.html"), "{}", stdout);
+    assert!(std::fs::read_to_string(folder.join("sheets.html")).unwrap().contains("Monkey.D.Luffy"));
+    assert!(!folder.join("binder.html").exists());
+}
+
+#[test]
+fn search_command_takes_filters()
+{
+    let folder = collection(&[]);
+
+    let output = run(&folder, &["search", "straw", "hat", "set=romance-dawn", "max=5"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(stdout.trim(), "1002 | Nami, \"Navigator\" OP01-016 | Romance Dawn | Rare | $3.25");
+
+    let output = run(&folder, &["search", "luffy", "page=2"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("1004 | Monkey.D.Luffy OP04-090"));
+    assert!(stdout.contains("page 2 of 2"));
+
+    let output = run(&folder, &["search", "luffy", "min=cheap"], "");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("cheap is not a price!"));
+
+    let output = run(&folder, &["search", "luffy", "artist=oda"], "");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("artist is not a filter!"));
+}
+
+#[test]
+fn removing_a_card_by_name()
+{
+    let folder = collection(&[1001, 1002, 1001]);
+
+    // only one nami, so it's picked straight away, then just removed
+    let output = run(&folder, &["classic"], "2\nnmai\ny\n3\n14\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("Are you sure you want to delete Nami, \"Navigator\" OP01-016?"));
+
+    let db = util::import(&folder.join("cards.json").to_string_lossy());
+    assert_eq!(db.cards.len(), 2);
+    assert!(db.cards.iter().all(|card| util::format_id(card.product_id.clone()) == "1001"));
+
+    // two luffys fit, so it asks which
+    let output = run(&folder, &["classic"], "2\nlufy\n1\ny\n3\n14\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("[0] Monkey.D.Luffy OP01-024"));
+    assert!(stdout.contains("[1] Monkey.D.Luffy OP01-024"));
+    assert_eq!(util::import(&folder.join("cards.json").to_string_lossy()).cards.len(), 1);
+}
+
+#[test]
+fn find_command_searches_without_tcgplayer()
+{
+    let folder = collection(&[1001]);
+
+    let output = run(&folder, &["find", "lufy"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(stdout.trim(), "1001 | Monkey.D.Luffy OP01-024 | Romance Dawn | owned 1");
+}
+
+#[test]
+fn query_command()
+{
+    let folder = collection(&[1001, 1002, 1001, 1003]);
+
+    let output = run(&folder, &["query", "type=character", "cost<=3", "subtype=straw", "hat", "crew"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("2x Monkey.D.Luffy OP01-024 | Red Character, cost 3, 5000 power, +1000 counter, Straw Hat Crew | Romance Dawn | $"), "{}", stdout);
+    assert!(stdout.contains("1x Nami, \"Navigator\" OP01-016 | Blue Character, cost 1, 2000 power"));
+    assert!(!stdout.contains("Zoro"));
+    assert!(stdout.contains("3 cards match type=character cost<=3 subtype=straw hat crew"));
+
+    let output = run(&folder, &["query", "cost=cheap"], "");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("cost is compared with a number"));
+}
+
+#[test]
+fn reports_only_cover_the_query()
+{
+    let folder = collection(&[1001, 1002, 1003]);
+
+    let output = run(&folder, &["report", "markdown", "color=red"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("(1 cards, by market price)"), "{}", stdout);
+    assert!(!stdout.contains("Nami"));
+
+    // a file name comes before the query
+    run(&folder, &["report", "markdown", "leaders.md", "type=leader"], "");
+    let saved = std::fs::read_to_string(folder.join("leaders.md")).unwrap();
+
+    assert!(saved.contains("Zoro"));
+    assert!(!saved.contains("Monkey.D.Luffy"));
+
+    // a typo in the query is reported rather than taken as a file name
+    let output = run(&folder, &["report", "markdown", "colr=red"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("colr isn't a field"), "{}", stdout);
+    assert!(!folder.join("colr=red").exists());
+
+    // and the menu asks for one, report, markdown, then print it here
+    let output = run(&folder, &["classic"], "3\n2\ncolor=blue\n2\n\n14\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("Only cards matching"));
+    assert!(stdout.contains("(1 cards, by market price)"), "{}", stdout);
+    assert!(stdout.contains("Nami"));
+}
+
+#[test]
+fn stats_command_shows_movers_since_the_last_snapshot()
+{
+    let folder = collection(&[1001, 1001, 1002, 1004]);
+
+    let output = run(&folder, &["stats"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("Total Worth: $29.00 (4 cards, by market price)"), "{}", stdout);
+    assert!(stdout.contains("No snapshot yet"));
+
+    // a snapshot from when luffy was cheaper
+    run(&folder, &["snapshot"], "");
+
+    let snapshot_path = std::fs::read_dir(folder.join("snapshots")).unwrap().next().unwrap().unwrap().path();
+    let mut snapshot: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&snapshot_path).unwrap()).unwrap();
+    snapshot["prices"]["1001"] = serde_json::json!(2.5);
+    std::fs::write(&snapshot_path, snapshot.to_string()).unwrap();
+
+    let output = run(&folder, &["stats"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("Largest Movers Since snapshot"));
+    assert!(stdout.contains("Monkey.D.Luffy OP01-024  $2.50  $12.50  2     +$20.00"), "{}", stdout);
+    assert!(!stdout.contains("Nami, \"Navigator\" OP01-016  $"), "nami's price didn't move");
+
+    let output = run(&folder, &["stats", "json"], "");
+    let stats: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_eq!(stats["card_count"], 4);
+    assert_eq!(stats["movers"]["cards"][0]["price_effect"], 20.0);
+    assert_eq!(stats["cost_curve"][3]["count"], 2);
+}
+
+#[test]
+fn stats_movers_only_cover_the_active_collection()
+{
+    let folder = common::scratch("cli");
+
+    let mut db = common::db(&[1001, 1001, 1002]);
+    db.collections.push("trades".to_string());
+    db.cards[1].collection = "trades".to_string();
+    db.cards[2].collection = "trades".to_string();
+    db.active = Some(util::DEFAULT_COLLECTION.to_string());
+    std::fs::write(folder.join("cards.json"), serde_json::to_string(&db).unwrap()).unwrap();
+
+    run(&folder, &["snapshot"], "");
+
+    // both cards were cheaper, but only one luffy is in the collection being looked at
+    let snapshot_path = std::fs::read_dir(folder.join("snapshots")).unwrap().next().unwrap().unwrap().path();
+    let mut snapshot: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&snapshot_path).unwrap()).unwrap();
+    snapshot["prices"]["1001"] = serde_json::json!(2.5);
+    snapshot["prices"]["1002"] = serde_json::json!(0.5);
+    std::fs::write(&snapshot_path, snapshot.to_string()).unwrap();
+
+    let output = run(&folder, &["stats"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("Monkey.D.Luffy OP01-024  $2.50  $12.50  1     +$10.00"), "{}", stdout);
+    assert!(!stdout.contains("Nami"), "{}", stdout);
+}
+
+#[test]
+fn diff_command_takes_html_after_one_snapshot()
+{
+    let folder = collection(&[1001]);
+
+    run(&folder, &["snapshot"], "");
+
+    // html is the format, the snapshot is compared with the collection right now
+    let output = run(&folder, &["diff", "0", "html"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("generated diff.html"), "{}", stdout);
+    assert!(std::fs::read_to_string(folder.join("diff.html")).unwrap().contains("now"));
+}
+
+#[test]
+fn playsets_command_and_have_list()
+{
+    let folder = collection(&[1001, 1001, 1001, 1001, 1001, 1001, 1002]);
+
+    let output = run(&folder, &["playsets"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("Surplus: 2 cards beyond a playset, worth $25.00"), "{}", stdout);
+    assert!(stdout.contains("    2x Monkey.D.Luffy OP01-024 (Romance Dawn, Super Rare) $12.50 each"));
+    assert!(stdout.contains("Nami, \"Navigator\" OP01-016: 1 owned, need 3 more, about $9.75 to finish"));
+
+    let output = run(&folder, &["playsets", "have", "have.txt"], "");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("saved have.txt"));
+
+    let have_list = std::fs::read_to_string(folder.join("have.txt")).unwrap();
+    assert!(have_list.contains("2x Monkey.D.Luffy OP01-024"));
+    assert!(!have_list.contains("Nami"));
+}
+
+#[test]
+fn opening_a_box()
+{
+    let folder = collection(&[]);
+
+    // open packs: the set, the cost, a box, the alt art luffy, nami by number only, a typo, then take one back
+    let input = "13\n1\nromance dawn\n$50\n\nOP01-024\n1\n016\n999\n016\n-\n\ny\n14\n";
+    let output = run(&folder, &["classic"], input);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("[1] Monkey.D.Luffy (Alternate Art) OP01-024 | Super Rare | $40.00"));
+    assert!(stdout.contains("No OP01-999 in romance dawn!"));
+    assert!(stdout.contains("took back Nami"));
+    assert!(stdout.contains("2 cards worth $43.25 for $50.00 (-$6.75)"));
+
+    let db = util::import(&folder.join("cards.json").to_string_lossy());
+    assert_eq!(db.cards.len(), 2);
+    assert_eq!(db.openings[0].packs, 24);
+    assert_eq!(db.openings[0].set_name, "Romance Dawn");
+
+    let output = run(&folder, &["openings"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("| Romance Dawn | 1 | 24 | $50.00 | $43.25 | $43.25 | -$6.75 (86%) |"), "{}", stdout);
+}
@@ -0,0 +1,63 @@
+//! what every test file shares: the mock tcgplayer, scratch folders and small databases
+
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::OnceLock;
+
+use onepiececards::settings::{self, Settings};
+use onepiececards::util::{self, Card, Database};
+use onepiececards::valuation::Condition;
+
+static MOCK_URL: OnceLock<String> = OnceLock::new();
+static SCRATCH_COUNT: AtomicU32 = AtomicU32::new(0);
+
+pub fn fixtures() -> PathBuf
+{
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures")
+}
+
+/// starts the mock once per test binary and points the library at it, along with an empty config folder
+pub fn mock() -> &'static str
+{
+    MOCK_URL.get_or_init(|| {
+        let url = onepiececards::mock::start(&fixtures(), 0).expect("the mock server should start");
+
+        std::env::set_var(util::API_URL_VAR, &url);
+        std::env::set_var(util::IMAGE_URL_VAR, &url);
+        std::env::set_var(settings::CONFIG_DIR_VAR, scratch("config"));
+
+        url
+    })
+}
+
+/// a new empty folder that no other test uses
+pub fn scratch(name: &str) -> PathBuf
+{
+    let folder = std::env::temp_dir().join(format!("onepiececards-test-{}-{}-{}", name, std::process::id(), SCRATCH_COUNT.fetch_add(1, Ordering::SeqCst)));
+
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(&folder).expect("the scratch folder should be created");
+
+    folder
+}
+
+/// every setting left at its default, without reading the user's settings file
+pub fn settings() -> Settings
+{
+    serde_json::from_str("{}").unwrap()
+}
+
+pub fn card(product_id: u64, condition: Condition) -> Card
+{
+    Card { product_id: product_id.into(), collection: util::DEFAULT_COLLECTION.to_string(), owner: String::new(), condition }
+}
+
+/// near mint copies of each product id
+pub fn db(product_ids: &[u64]) -> Database
+{
+    let mut db = util::empty_db();
+    db.cards = product_ids.iter().map(|product_id| card(*product_id, Condition::NearMint)).collect();
+    db
+}
@@ -0,0 +1,64 @@
+//! showing dollars in another currency, kept apart since it points the rate table at a dead address
+
+mod common;
+
+use onepiececards::currency::{self, Rates};
+use onepiececards::{settings, util};
+
+#[test]
+fn empty_sums_show_without_a_sign()
+{
+    let empty: f64 = Vec::<f64>::new().into_iter().sum();
+
+    assert_eq!(currency::format(&currency::usd(), empty), "$0.00");
+    assert_eq!(currency::format(&currency::usd(), -0.001), "$0.00");
+    assert_eq!(currency::format(&currency::usd(), -1.5), "-$1.50");
+    assert_eq!(currency::signed(&currency::usd(), empty), "+$0.00");
+}
+
+#[test]
+fn failed_rate_fetches_wait_before_trying_again()
+{
+    let config = common::scratch("config");
+    std::env::set_var(settings::CONFIG_DIR_VAR, &config);
+    std::env::set_var(currency::RATES_URL_VAR, "http://127.0.0.1:9");
+
+    let mut settings = common::settings();
+    settings.currency = "EUR".to_string();
+
+    let rates_path = config.join("rates.json");
+    let failed_at = |path: &std::path::Path| serde_json::from_str::<Rates>(&std::fs::read_to_string(path).unwrap()).unwrap().failed;
+
+    // nothing saved and nothing fetched, so dollars, with the failure remembered
+    assert_eq!(currency::load(&settings).code, "USD");
+    let first_failure = failed_at(&rates_path).expect("the failure should be saved");
+
+    // the next load leaves the dead address alone
+    assert_eq!(currency::load(&settings).code, "USD");
+    assert_eq!(failed_at(&rates_path), Some(first_failure));
+
+    // old rates saved before the failure are still used meanwhile
+    std::fs::write(&rates_path, format!(r#"{{"base":"USD","date":"2026-10-01","rates":{{"EUR":0.9}},"failed":{}}}"#, serde_json::to_string(&first_failure).unwrap())).unwrap();
+
+    let euros = currency::load(&settings);
+
+    assert_eq!(euros.code, "EUR");
+    assert_eq!(currency::format(&euros, 10.0), "€9.00");
+}
+
+#[test]
+fn rates_saved_in_the_future_count_as_stale()
+{
+    let rates_path = common::scratch("rates").join("rates.json");
+    std::fs::write(&rates_path, "{}").unwrap();
+
+    let path = rates_path.to_string_lossy();
+    assert!(!util::cache_too_old(util::cache_old(&path)));
+
+    // copied from a computer whose clock runs a day ahead
+    let file = std::fs::File::options().write(true).open(&rates_path).unwrap();
+    file.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(24 * 60 * 60)).unwrap();
+
+    assert!(util::cache_too_old(util::cache_old(&path)));
+    assert_eq!(util::cache_old("no such file"), u64::MAX);
+}
@@ -0,0 +1,36 @@
+//! spreadsheet and tcgplayer mass entry exports, priced by the mock
+
+mod common;
+
+use std::collections::HashMap;
+
+use onepiececards::{currency, export};
+use onepiececards::valuation::{Condition, PriceBasis};
+
+#[test]
+fn conditions_get_their_own_rows_valued_like_the_reports()
+{
+    common::mock();
+
+    let mut db = common::db(&[1001, 1001]);
+    db.cards.push(common::card(1001, Condition::Damaged));
+
+    let mut settings = common::settings();
+    settings.price_basis = PriceBasis::Low;
+
+    let (csv, webcache) = export::csv(&db, HashMap::new(), &settings, &currency::usd());
+    let lines: Vec<&str> = csv.lines().collect();
+
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].contains(",Rarity,Condition,"));
+
+    // two near mint copies at the lowest listing, then one damaged copy at 0.3 of it
+    assert!(lines[1].starts_with("2,1001,Monkey.D.Luffy,OP01-024,Romance Dawn,OP01,Super Rare,near mint,"), "{}", lines[1]);
+    assert!(lines[1].contains(",12.50,10.00,12.50,20.00,USD,"), "{}", lines[1]);
+    assert!(lines[2].starts_with("1,1001,") && lines[2].contains(",damaged,"), "{}", lines[2]);
+    assert!(lines[2].contains(",12.50,10.00,12.50,3.00,USD,"), "{}", lines[2]);
+
+    // the mass entry box has no condition, so the copies come back together
+    let (mass_entry, _) = export::tcgplayer(&db, webcache, &settings);
+    assert_eq!(mass_entry, "3 Monkey.D.Luffy [OP01]\n");
+}
@@ -0,0 +1,111 @@
+//! finding cards we've already looked up, typos and all
+
+mod common;
+
+use std::collections::HashMap;
+
+use onepiececards::index;
+use onepiececards::util;
+
+fn details(product_id: u64) -> String
+{
+    std::fs::read_to_string(common::fixtures().join("details").join(format!("{}.json", product_id))).unwrap()
+}
+
+// the index over a cache holding every product's details, owning a luffy twice
+fn entries() -> Vec<index::Entry>
+{
+    common::mock();
+
+    let mut webcache = HashMap::new();
+
+    for product_id in [1001, 1002, 1003, 1004]
+    {
+        webcache.insert(format!("get_product_details:{}", product_id), details(product_id));
+    }
+
+    let (entries, webcache) = index::build(&common::db(&[1001, 1001, 1003]), webcache);
+
+    assert_eq!(webcache.len(), 4, "nothing should have been fetched");
+
+    entries
+}
+
+fn found(query: &str) -> Vec<String>
+{
+    index::find(&entries(), query).into_iter().map(|found| found.entry.product_id).collect()
+}
+
+#[test]
+fn exact_names_and_codes_are_found()
+{
+    assert_eq!(found("Nami Navigator"), ["1002"]);
+    assert_eq!(found("OP01-024"), ["1001"]);
+    assert_eq!(found("OP04"), ["1004"]);
+}
+
+#[test]
+fn typos_and_half_typed_words_still_match()
+{
+    // both luffys fit, the one we own comes first
+    assert_eq!(found("lufy"), ["1001", "1004"]);
+    assert_eq!(found("nmai"), ["1002"]);
+    assert_eq!(found("zor"), ["1003"]);
+    assert_eq!(found("luffy romace"), ["1001"]);
+}
+
+#[test]
+fn set_names_and_effects_are_searched()
+{
+    assert_eq!(found("paramount war"), ["1003"]);
+    assert_eq!(found("draw 1 card").len(), 4);
+}
+
+#[test]
+fn nonsense_finds_nothing()
+{
+    assert!(found("xyzzy").is_empty());
+    assert!(found("").is_empty());
+}
+
+#[test]
+fn owned_cards_and_search_results_are_indexed()
+{
+    common::mock();
+
+    // 1002 is only owned, so its details get looked up, the rest come from a cached search
+    let (_, webcache) = util::search("straw hat", HashMap::new());
+    let (entries, webcache) = index::build(&common::db(&[1002, 1002]), webcache);
+
+    assert!(webcache.contains_key("get_product_details:1002"));
+    assert_eq!(entries.len(), 4);
+
+    let nami = entries.iter().find(|entry| entry.product_id == "1002").unwrap();
+
+    assert_eq!(nami.owned, 2);
+    assert_eq!(nami.number, "OP01-016");
+    assert_eq!(nami.description, "[On Play] Draw 1 card.");
+}
+
+#[test]
+fn new_searches_add_to_the_index()
+{
+    common::mock();
+
+    let db = common::db(&[1002]);
+    let (mut entries, webcache) = index::build(&db, HashMap::new());
+
+    assert_eq!(entries.len(), 1);
+
+    let (body, _) = util::search("straw hat", webcache);
+    index::add_search(&mut entries, &body, &db);
+
+    assert_eq!(entries.len(), 4);
+    assert_eq!(entries.iter().filter(|entry| entry.product_id == "1002").count(), 1, "cards already indexed shouldn't be added twice");
+    assert_eq!(index::find(&entries, "zoro")[0].entry.product_id, "1003");
+
+    index::recount(&mut entries, &common::db(&[1003, 1003]));
+
+    assert_eq!(entries.iter().find(|entry| entry.product_id == "1003").unwrap().owned, 2);
+    assert_eq!(entries.iter().find(|entry| entry.product_id == "1002").unwrap().owned, 0);
+}
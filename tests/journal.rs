@@ -0,0 +1,49 @@
+//! the change log and undo/redo
+
+mod common;
+
+use onepiececards::{collection, journal};
+use onepiececards::valuation::Condition;
+
+#[test]
+fn undo_and_redo_stay_on_the_active_collection()
+{
+    let journal_path = common::scratch("journal").join("journal.jsonl").to_string_lossy().to_string();
+    let mut history = journal::History::default();
+
+    let mut before = common::db(&[1001]);
+    assert!(collection::create(&mut before, "binder"));
+
+    let mut db = before.clone();
+    db.cards.push(common::card(1002, Condition::NearMint));
+    assert!(journal::record(&mut history, &before, &db, &journal_path));
+
+    // looking at another binder isn't a change, the cards added before it still get undone
+    assert!(collection::switch(&mut db, Some("binder")));
+
+    let undone = journal::undo(&mut history, &db, &journal_path).unwrap();
+    assert_eq!(undone.cards.len(), 1);
+    assert_eq!(undone.active.as_deref(), Some("binder"));
+
+    let redone = journal::redo(&mut history, &undone, &journal_path).unwrap();
+    assert_eq!(redone.cards.len(), 2);
+    assert_eq!(redone.active.as_deref(), Some("binder"));
+}
+
+#[test]
+fn condition_changes_say_what_an_undo_would_put_back()
+{
+    let journal_path = common::scratch("journal").join("journal.jsonl").to_string_lossy().to_string();
+    let mut history = journal::History::default();
+
+    let before = common::db(&[1001, 1001]);
+    let mut after = before.clone();
+    after.cards[1].condition = Condition::Damaged;
+
+    assert!(journal::record(&mut history, &before, &after, &journal_path));
+
+    let entries = journal::entries(&journal_path);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].description, "changed condition of 1x 1001 from near mint to damaged");
+    assert!(entries[0].action == journal::Action::Edit);
+}
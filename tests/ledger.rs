@@ -0,0 +1,45 @@
+//! the sales ledger, from archived cards to realized profit
+
+mod common;
+
+use chrono::NaiveDate;
+
+use onepiececards::ledger::{self, Disposal, DisposalKind};
+use onepiececards::valuation::Condition;
+
+fn sale(product_id: u64, date: &str, sale_price: f64, purchase_cost: Option<f64>) -> Disposal
+{
+    Disposal {
+        card: common::card(product_id, Condition::NearMint),
+        name: "Monkey.D.Luffy".to_string(),
+        kind: DisposalKind::Sold,
+        date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+        sale_price,
+        fees: 1.5,
+        shipping: 0.5,
+        counterparty: String::new(),
+        purchase_cost
+    }
+}
+
+#[test]
+fn revenue_is_what_was_left_after_fees_and_shipping()
+{
+    let mut db = common::db(&[1001]);
+
+    assert!(ledger::archive(&mut db, 0, sale(1001, "2024-08-02", 12.0, Some(5.0))));
+    db.archive.push(sale(1001, "2024-09-10", 20.0, None));
+
+    let lines = ledger::by_card(&db);
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].count, 2);
+    assert_eq!(lines[0].revenue, 28.0);
+    assert_eq!(lines[0].profit, 23.0);
+    assert!(lines[0].missing_cost);
+
+    let months = ledger::by_period(&db);
+    assert_eq!(months.iter().map(|line| (line.label.as_str(), line.revenue)).collect::<Vec<_>>(), [("2024-08", 10.0), ("2024-09", 18.0)]);
+
+    assert_eq!(ledger::total(&months), 23.0);
+    assert!(db.cards.is_empty());
+}
@@ -0,0 +1,194 @@
+//! product details, searches and images through the web cache, answered by the mock
+
+mod common;
+
+use std::collections::HashMap;
+
+use onepiececards::util::{self, SearchFilters};
+use serde_json::Value;
+
+#[test]
+fn details_are_fetched_and_cached()
+{
+    common::mock();
+
+    let (body, webcache) = util::get_product_details("1001", HashMap::new());
+    let product_info: Value = serde_json::from_str(&body).unwrap();
+
+    assert_eq!(product_info["productName"], "Monkey.D.Luffy");
+    assert_eq!(util::display_name(&product_info), "Monkey.D.Luffy OP01-024");
+    assert_eq!(webcache.get("get_product_details:1001"), Some(&body));
+}
+
+#[test]
+fn cached_details_are_not_fetched_again()
+{
+    common::mock();
+
+    // nothing in the fixtures answers 9999, so this can only come from the cache
+    let mut webcache = HashMap::new();
+    webcache.insert("get_product_details:9999".to_string(), r#"{"productName":"Cached"}"#.to_string());
+
+    let (body, webcache) = util::get_product_details("9999", webcache);
+
+    assert_eq!(body, r#"{"productName":"Cached"}"#);
+    assert_eq!(webcache.len(), 1);
+}
+
+#[test]
+fn missing_products_are_not_cached()
+{
+    common::mock();
+
+    let (body, webcache) = util::get_product_details("9999", HashMap::new());
+
+    assert!(body.is_empty());
+    assert!(webcache.is_empty());
+}
+
+#[test]
+fn search_only_keeps_what_it_was_asked()
+{
+    common::mock();
+
+    let (body, webcache) = util::search("luffy", HashMap::new());
+    let response: Value = serde_json::from_str(&body).unwrap();
+    let products = response["results"][0]["results"].as_array().unwrap();
+
+    // searches only ask for one piece cards, so the sleeves in the fixture never come back
+    assert_eq!(products.len(), 1);
+    assert_eq!(products[0]["productId"], 1001);
+    assert!(webcache.contains_key("search:luffy"));
+
+    let (body, webcache) = util::search("nobody at all", webcache);
+
+    assert!(body.is_empty());
+    assert_eq!(webcache.len(), 1);
+}
+
+#[test]
+fn images_come_back_as_base64_jpegs()
+{
+    common::mock();
+
+    let (image_b64, webcache) = util::card_image_b64("1001", HashMap::new());
+    let thumbnail = onepiececards::images::thumbnail(&image_b64);
+
+    assert!(thumbnail.is_some_and(|jpeg| jpeg.starts_with(&[0xFF, 0xD8])));
+    assert!(webcache.contains_key("card_image_b64:1001"));
+
+    let (image_b64, webcache) = util::card_image_b64("1002", webcache);
+
+    assert!(image_b64.is_empty());
+    assert_eq!(webcache.len(), 1);
+}
+
+#[test]
+fn search_names_are_encoded()
+{
+    common::mock();
+
+    // unencoded, the & and # would cut the name down to plain "luffy" and get its results instead
+    let (body, _) = util::search("Luffy & Ace #1", HashMap::new());
+    let response: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(response["results"][0]["results"][0]["productId"], 1004);
+
+    let (body, _) = util::search("ルフィ", HashMap::new());
+    assert!(!body.is_empty());
+}
+
+#[test]
+fn searches_come_in_pages()
+{
+    common::mock();
+
+    let (body, webcache) = util::search_page("luffy", 1, &SearchFilters::default(), HashMap::new());
+    let response: Value = serde_json::from_str(&body).unwrap();
+
+    assert_eq!(response["results"][0]["results"][0]["productId"], 1004);
+    assert_eq!(util::search_pages(&response), 2);
+    assert!(webcache.contains_key("search:luffy:page1"));
+
+    // the first page is what plain search asks for
+    let (first, webcache) = util::search_page("luffy", 0, &SearchFilters::default(), webcache);
+    let (plain, webcache) = util::search("luffy", webcache);
+
+    assert_eq!(first, plain);
+    assert_eq!(webcache.len(), 2);
+
+    let (past_the_end, _) = util::search_page("luffy", 2, &SearchFilters::default(), webcache);
+    assert!(past_the_end.is_empty());
+}
+
+// the product ids a filtered search of the straw hat fixture comes back with
+fn filtered(filters: &[(&str, &str)]) -> Vec<u64>
+{
+    common::mock();
+
+    let mut search_filters = SearchFilters::default();
+
+    for (name, value) in filters
+    {
+        assert!(util::search_filter(&mut search_filters, name, value));
+    }
+
+    let (body, webcache) = util::search_page("straw hat", 0, &search_filters, HashMap::new());
+    let response: Value = serde_json::from_str(&body).unwrap();
+
+    assert_eq!(webcache.len(), 1);
+
+    response["results"][0]["results"].as_array().unwrap().iter().map(|product| product["productId"].as_u64().unwrap()).collect()
+}
+
+#[test]
+fn searches_can_be_filtered()
+{
+    assert_eq!(filtered(&[]), [1001, 1002, 1003, 1004]);
+    assert_eq!(filtered(&[("set", "Romance Dawn")]), [1001, 1002]);
+    assert_eq!(filtered(&[("set", "romance-dawn"), ("rarity", "super rare")]), [1001]);
+    assert_eq!(filtered(&[("color", "Green")]), [1003]);
+    assert_eq!(filtered(&[("type", "Leader")]), [1003, 1004]);
+    assert_eq!(filtered(&[("min", "1"), ("max", "$5")]), [1002]);
+    assert_eq!(filtered(&[("type", "Leader"), ("max", "5")]), [1004]);
+}
+
+#[test]
+fn filtered_searches_are_cached_apart()
+{
+    common::mock();
+
+    let mut leaders = SearchFilters::default();
+    util::search_filter(&mut leaders, "type", "Leader");
+
+    let (all, webcache) = util::search("straw hat", HashMap::new());
+    let (only_leaders, webcache) = util::search_page("straw hat", 0, &leaders, webcache);
+
+    assert_ne!(all, only_leaders);
+    assert_eq!(webcache.len(), 2);
+}
+
+#[test]
+fn search_filters_read_names_and_prices()
+{
+    let mut filters = SearchFilters::default();
+
+    assert!(util::search_filter(&mut filters, "set", " Romance Dawn "));
+    assert!(util::search_filter(&mut filters, "max", "$7.50"));
+    assert!(!util::search_filter(&mut filters, "min", "cheap"));
+    assert!(!util::search_filter(&mut filters, "artist", "oda"));
+
+    assert_eq!(util::describe_filters(&filters), "set Romance Dawn, at most $7.50");
+
+    assert!(util::search_filter(&mut filters, "set", ""));
+    assert_eq!(filters.set, None);
+
+    assert_eq!(util::url_name("Kingdoms of Intrigue"), "kingdoms-of-intrigue");
+}
+
+#[test]
+fn search_files_are_named_after_the_query()
+{
+    assert_eq!(onepiececards::mock::search_file("Luffy OP01", 0), "luffy_op01");
+    assert_eq!(onepiececards::mock::search_file(" nami/robin ", 0), "nami_robin");
+    assert_eq!(onepiececards::mock::search_file("Luffy", 2), "luffy-page2");
+}
@@ -0,0 +1,63 @@
+//! what happens when tcgplayer can't be reached at all, kept apart since it points the library at a dead address
+
+mod common;
+
+use std::collections::HashMap;
+
+use onepiececards::{report, settings, util, valuation};
+
+// nothing listens on port 9 of the local machine, so every request fails straight away
+fn unreachable()
+{
+    std::env::set_var(util::API_URL_VAR, "http://127.0.0.1:9");
+    std::env::set_var(util::IMAGE_URL_VAR, "http://127.0.0.1:9");
+    std::env::set_var(settings::CONFIG_DIR_VAR, common::scratch("config"));
+}
+
+#[test]
+fn failed_lookups_come_back_empty_and_uncached()
+{
+    unreachable();
+
+    let (body, webcache) = util::get_product_details("1001", HashMap::new());
+    assert!(body.is_empty());
+
+    let (body, webcache) = util::search("luffy", webcache);
+    assert!(body.is_empty());
+
+    let (body, webcache) = util::card_image_b64("1001", webcache);
+    assert!(body.is_empty());
+
+    // the try_ versions say why, for whoever's showing it
+    let (body, webcache) = util::try_get_product_details("1001", webcache);
+    assert!(body.unwrap_err().contains("could not reach tcgplayer"));
+
+    assert!(webcache.is_empty());
+}
+
+#[test]
+fn the_cache_still_answers_while_offline()
+{
+    unreachable();
+
+    let mut webcache = HashMap::new();
+    webcache.insert("get_product_details:1001".to_string(), std::fs::read_to_string(common::fixtures().join("details").join("1001.json")).unwrap());
+
+    let (valuation, _) = valuation::value(&common::db(&[1001, 1002]), webcache, &common::settings());
+
+    assert_eq!(valuation.total, 12.5);
+    assert_eq!(valuation.unknown, 1);
+}
+
+#[test]
+fn reports_still_work_without_prices()
+{
+    unreachable();
+
+    let (data, webcache) = report::gather(&common::db(&[1001, 1003]), HashMap::new(), &common::settings());
+
+    assert_eq!(data.card_count, 2);
+    assert_eq!(data.unknown_prices, 2);
+    assert_eq!(data.total_worth, 0.0);
+    assert!(webcache.is_empty());
+}
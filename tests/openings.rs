@@ -0,0 +1,100 @@
+//! booster opening sessions, looked up by OP code and compared with what they cost
+
+mod common;
+
+use std::collections::HashMap;
+
+use chrono::{Duration, Local};
+use onepiececards::opening::{self, Opening, Pull};
+use onepiececards::util::{self, format_id};
+
+fn pull(product_id: u64, price: f64) -> Pull
+{
+    Pull { product_id: product_id.into(), name: String::new(), set_name: String::new(), rarity: String::new(), price: Some(price) }
+}
+
+#[test]
+fn codes_borrow_the_set_from_the_last_one()
+{
+    assert_eq!(opening::full_code("op01-024", None).as_deref(), Some("OP01-024"));
+    assert_eq!(opening::full_code(" 016 ", Some("OP01-024")).as_deref(), Some("OP01-016"));
+    assert_eq!(opening::full_code("016", None), None);
+}
+
+#[test]
+fn printings_are_found_by_code_in_the_set()
+{
+    common::mock();
+
+    let (printings, webcache) = opening::printings("OP01-024", "Romance Dawn", HashMap::new());
+
+    // nami comes back from the search too, but has another number
+    let names: Vec<&str> = printings.iter().map(|printing| printing.name.as_str()).collect();
+    assert_eq!(names, vec!["Monkey.D.Luffy OP01-024", "Monkey.D.Luffy (Alternate Art) OP01-024"]);
+    assert_eq!(printings[1].price, Some(40.0));
+    assert_eq!(printings[1].set_name, "Romance Dawn");
+
+    let (printings, _) = opening::printings("OP01-024", "Paramount War", webcache);
+    assert!(printings.is_empty());
+}
+
+#[test]
+fn saving_adds_the_pulls_to_the_collection()
+{
+    let mut db = common::db(&[1003]);
+    db.active = Some("boxes".to_string());
+
+    opening::save(&mut db, Opening { date: Local::now(), set_name: "Romance Dawn".to_string(), packs: 24, cost: 90.0, pulls: vec![pull(1005, 40.0), pull(1002, 3.25)] });
+
+    assert_eq!(db.cards.len(), 3);
+    assert_eq!(format_id(db.cards[1].product_id.clone()), "1005");
+    assert_eq!(db.cards[2].collection, "boxes");
+    assert_eq!(db.openings.len(), 1);
+    assert_eq!(opening::value(&db.openings[0].pulls), 43.25);
+
+    // and it survives a save
+    let path = common::scratch("openings").join("cards.json");
+    assert!(util::save_db(db, &path.to_string_lossy()));
+    assert_eq!(util::import(&path.to_string_lossy()).openings[0].pulls.len(), 2);
+}
+
+#[test]
+fn summary_compares_cost_with_value_then_and_now()
+{
+    common::mock();
+
+    let mut db = common::db(&[]);
+
+    // the alt art was pulled at $30, it's $40 now, and the luffy leader from another set
+    opening::save(&mut db, Opening { date: Local::now(), set_name: "Romance Dawn".to_string(), packs: 24, cost: 50.0, pulls: vec![pull(1005, 30.0), pull(1002, 3.25)] });
+    opening::save(&mut db, Opening { date: Local::now() - Duration::days(7), set_name: "Romance Dawn".to_string(), packs: 4, cost: 20.0, pulls: vec![pull(1001, 12.5)] });
+    opening::save(&mut db, Opening { date: Local::now(), set_name: "Kingdoms of Intrigue".to_string(), packs: 1, cost: 5.0, pulls: vec![pull(1004, 0.75)] });
+
+    let (summary, _) = opening::summary(&db, HashMap::new());
+
+    // oldest first
+    assert_eq!(summary.sessions[0].packs, 4);
+    assert_eq!((summary.sessions[1].value_then, summary.sessions[1].value_now), (33.25, 43.25));
+
+    let romance_dawn = summary.sets.iter().find(|set| set.set_name == "Romance Dawn").unwrap();
+    assert_eq!((romance_dawn.sessions, romance_dawn.packs, romance_dawn.cost), (2, 28, 70.0));
+    assert_eq!((romance_dawn.value_then, romance_dawn.value_now), (45.75, 55.75));
+
+    assert_eq!(summary.sets.len(), 2);
+    assert_eq!((summary.cost, summary.value_then), (75.0, 46.5));
+
+    let output = opening::text(&summary, &onepiececards::currency::usd());
+    assert!(output.contains("| Romance Dawn | 2 | 28 | $70.00 | $45.75 | $55.75 | -$24.25 (65%) |"), "{}", output);
+}
+
+#[test]
+fn an_empty_history_adds_up_to_nothing()
+{
+    let (summary, _) = opening::summary(&common::db(&[]), HashMap::new());
+
+    assert!(summary.sessions.is_empty());
+
+    let output = opening::text(&summary, &onepiececards::currency::usd());
+    assert!(output.contains("Total: $0.00 spent, $0.00 pulled ($0.00 now), +$0.00"), "{}", output);
+    assert!(!output.contains("-0.00"), "{}", output);
+}
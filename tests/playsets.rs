@@ -0,0 +1,89 @@
+//! surplus beyond a playset, across printings, and the have list
+
+mod common;
+
+use std::collections::HashMap;
+
+use onepiececards::playset;
+use onepiececards::util::Database;
+use onepiececards::valuation::{Condition, PriceBasis};
+
+// three luffys and two alt art luffys (one played) share OP01-024, two zoro leaders, one nami and one purple luffy leader
+fn collection() -> Database
+{
+    common::mock();
+
+    let mut db = common::db(&[1001, 1001, 1005, 1002, 1003, 1003, 1004, 1001]);
+    db.cards.push(common::card(1005, Condition::LightlyPlayed));
+    db
+}
+
+#[test]
+fn surplus_is_counted_across_printings()
+{
+    let (analysis, _) = playset::analyse(&collection(), HashMap::new(), &common::settings());
+
+    // the cheapest four make the playset, so the near mint alt art is what's spare
+    let luffy = &analysis.surplus[0];
+
+    assert_eq!(luffy.number, "OP01-024");
+    assert_eq!((luffy.owned, luffy.playset), (5, 4));
+    assert_eq!(luffy.copies.len(), 1);
+    assert_eq!(luffy.copies[0].name, "Monkey.D.Luffy (Alternate Art) OP01-024");
+    assert!(luffy.copies[0].condition == Condition::NearMint);
+    assert_eq!(luffy.value, 40.0);
+
+    // a leader's playset is one, and an unpriced surplus still shows
+    let zoro = &analysis.surplus[1];
+
+    assert_eq!(zoro.number, "OP02-001");
+    assert_eq!((zoro.owned, zoro.playset, zoro.value), (2, 1, 0.0));
+
+    assert_eq!(analysis.surplus.len(), 2);
+    assert_eq!((analysis.surplus_count, analysis.surplus_value), (2, 40.0));
+}
+
+#[test]
+fn surplus_is_ranked_at_market_price_whatever_the_basis()
+{
+    let mut settings = common::settings();
+    settings.price_basis = PriceBasis::Low;
+
+    let (analysis, _) = playset::analyse(&collection(), HashMap::new(), &settings);
+
+    assert_eq!(analysis.surplus[0].number, "OP01-024");
+    assert_eq!(analysis.surplus_value, 40.0);
+
+    let (have_list, _) = playset::have_list(&collection(), HashMap::new(), &settings);
+    assert!(have_list.contains("2 cards, $40.00 at market price"), "{}", have_list);
+}
+
+#[test]
+fn short_playsets_say_what_finishing_costs()
+{
+    let (analysis, _) = playset::analyse(&collection(), HashMap::new(), &common::settings());
+
+    assert_eq!(analysis.short.len(), 1, "a single leader is already a playset");
+
+    let nami = &analysis.short[0];
+
+    assert_eq!(nami.number, "OP01-016");
+    assert_eq!((nami.owned, nami.missing), (1, 3));
+    assert_eq!(nami.cost, Some(9.75));
+}
+
+#[test]
+fn have_list_lists_every_spare_copy()
+{
+    let (have_list, _) = playset::have_list(&collection(), HashMap::new(), &common::settings());
+
+    let lines: Vec<&str> = have_list.lines().collect();
+
+    assert!(lines[0].starts_with("One Piece TCG have list, "));
+    assert_eq!(lines[2], "1x Monkey.D.Luffy (Alternate Art) OP01-024 (Romance Dawn, Super Rare) $40.00 each");
+    assert_eq!(lines[3], "1x Zoro OP02-001 (Paramount War, Leader) no price");
+    assert_eq!(lines[5], "2 cards, $40.00 at market price");
+
+    let (have_list, _) = playset::have_list(&common::db(&[1001, 1002]), HashMap::new(), &common::settings());
+    assert!(have_list.contains("Nothing spare right now!"));
+}
@@ -0,0 +1,94 @@
+//! reading card game data out of the product details and querying the collection with it
+
+mod common;
+
+use std::collections::HashMap;
+
+use onepiececards::{gamedata, query};
+
+fn card(product_id: u64) -> gamedata::CardData
+{
+    common::mock();
+
+    gamedata::lookup(&product_id.to_string(), HashMap::new()).0
+}
+
+fn fits(product_id: u64, text: &str) -> bool
+{
+    query::matches(&card(product_id), &query::parse(text).unwrap())
+}
+
+#[test]
+fn custom_attributes_are_parsed()
+{
+    let luffy = card(1001);
+
+    assert_eq!(luffy.number, "OP01-024");
+    assert_eq!(luffy.card_type, "Character");
+    assert_eq!(luffy.colors, vec!["Red"]);
+    assert_eq!(luffy.subtypes, vec!["Straw Hat Crew"]);
+    assert_eq!((luffy.cost, luffy.power, luffy.counter, luffy.life), (Some(3), Some(5000), Some(1000), None));
+    assert_eq!(luffy.effect, "[On Play] Draw 1 card.");
+    assert_eq!(gamedata::summary(&luffy), "Red Character, cost 3, 5000 power, +1000 counter, Straw Hat Crew");
+
+    // leaders have life instead of a cost and "-" for a counter, several subtypes share one string
+    let zoro = card(1003);
+
+    assert_eq!((zoro.cost, zoro.counter, zoro.life), (None, None, Some(5)));
+    assert_eq!(zoro.subtypes, vec!["Supernovas", "Straw Hat Crew"]);
+    assert_eq!(card(1004).subtypes, vec!["Straw Hat Crew", "Four Emperors"]);
+}
+
+#[test]
+fn queries_are_parsed()
+{
+    let clauses = query::parse("color=red subtype=straw hat crew counter>=1000").unwrap();
+
+    assert_eq!(clauses.len(), 3);
+    assert_eq!(clauses[1].value, "straw hat crew");
+    assert!(clauses[2].operator == query::Operator::GreaterOrEqual);
+    assert_eq!(query::describe(&clauses), "color=red subtype=straw hat crew counter>=1000");
+
+    assert!(query::parse("").unwrap().is_empty());
+    assert!(query::parse("red").unwrap_err().contains("red isn't a query"));
+    assert!(query::parse("cost>=five").unwrap_err().contains("cost is compared with a number"));
+    assert!(query::parse("color>red").unwrap_err().contains("color is text"));
+    assert!(query::parse("power=").unwrap_err().contains("power= needs a value"));
+
+    // a misspelled field isn't taken as more of the value before it
+    assert!(query::parse("cost=5 colour=red").unwrap_err().contains("colour isn't a field"));
+    assert!(query::parse("type=leader cost >=5").unwrap_err().contains(">=5 needs a field"));
+}
+
+#[test]
+fn cards_match_every_clause()
+{
+    assert!(fits(1001, "color=red subtype=straw hat crew counter=1000"));
+    assert!(fits(1001, "type=character cost=3"));
+    assert!(!fits(1001, "color=red cost=5"));
+    assert!(fits(1002, "cost<=1 color!=red"));
+    assert!(fits(1003, "subtype=supernovas life>4"));
+    assert!(fits(1004, "subtype~emperor set=kingdoms-of-intrigue"));
+    assert!(fits(1004, "name~luffy number=op04-090"));
+
+    // a leader has no cost, so it's only ever "not" a cost
+    assert!(!fits(1004, "cost<5"));
+    assert!(fits(1004, "cost!=5"));
+}
+
+#[test]
+fn filtering_keeps_every_copy_that_matches()
+{
+    common::mock();
+
+    let db = common::db(&[1001, 1002, 1001, 1003, 1004]);
+
+    let (leaders, webcache) = query::filter(&db, &query::parse("type=leader").unwrap(), HashMap::new());
+    let (straw_hats, _) = query::filter(&db, &query::parse("subtype=straw hat crew").unwrap(), webcache);
+    let (red, _) = query::filter(&db, &query::parse("color=red").unwrap(), HashMap::new());
+
+    assert_eq!(leaders.cards.len(), 2);
+    assert_eq!(straw_hats.cards.len(), 5);
+    assert_eq!(red.cards.len(), 2);
+    assert_eq!(query::filter(&db, &[], HashMap::new()).0.cards.len(), 5);
+}
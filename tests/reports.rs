@@ -0,0 +1,112 @@
+//! valuing the collection and every kind of report, priced by the mock
+
+mod common;
+
+use std::collections::HashMap;
+
+use onepiececards::settings::ReportMode;
+use onepiececards::valuation::{self, Condition, PriceBasis};
+use onepiececards::{html, report, text};
+
+#[test]
+fn valuation_totals_each_card_and_set()
+{
+    common::mock();
+
+    let (valuation, webcache) = valuation::value(&common::db(&[1001, 1002, 1001, 1003]), HashMap::new(), &common::settings());
+
+    assert_eq!(valuation.card_count, 4);
+    assert_eq!(valuation.total, 28.25);
+    assert_eq!(valuation.unknown, 1);
+    assert_eq!(valuation.cards[1].name, "Nami, \"Navigator\" OP01-016");
+
+    let romance_dawn = valuation.sets.iter().find(|set| set.name == "Romance Dawn").unwrap();
+    assert_eq!(romance_dawn.count, 3);
+    assert_eq!(romance_dawn.value, 28.25);
+
+    // one lookup per product, the repeated luffy comes from the cache
+    assert_eq!(webcache.len(), 3);
+}
+
+#[test]
+fn valuation_follows_the_basis_and_condition()
+{
+    common::mock();
+
+    let mut db = common::db(&[]);
+    db.cards.push(common::card(1001, Condition::Damaged));
+
+    let mut settings = common::settings();
+    settings.price_basis = PriceBasis::Low;
+
+    let (valuation, _) = valuation::value(&db, HashMap::new(), &settings);
+
+    assert_eq!(valuation.cards[0].unit_price, Some(10.0));
+    assert_eq!(valuation.total, 3.0);
+}
+
+#[test]
+fn text_reports_list_the_collection()
+{
+    common::mock();
+
+    let (data, _) = report::gather(&common::db(&[1001, 1002, 1003]), HashMap::new(), &common::settings());
+
+    assert_eq!(data.total_worth, 15.75);
+    assert_eq!(data.unknown_prices, 1);
+    assert_eq!(data.top[0].name, "Monkey.D.Luffy OP01-024");
+
+    let markdown = text::render(&data, text::Format::Markdown);
+
+    assert!(markdown.contains("Total Worth: $15.75 (3 cards, by market price)"));
+    assert!(markdown.contains("1 cards have no price"));
+    assert!(markdown.contains("Monkey.D.Luffy OP01-024"));
+    assert!(markdown.contains("Zoro OP02-001"));
+}
+
+#[test]
+fn copies_in_different_conditions_get_their_own_rows()
+{
+    common::mock();
+
+    let mut db = common::db(&[1001, 1001]);
+    db.cards.push(common::card(1001, Condition::Damaged));
+
+    let (data, _) = report::gather(&db, HashMap::new(), &common::settings());
+    let markdown = text::render(&data, text::Format::Markdown);
+
+    assert!(markdown.contains("| 2 | Monkey.D.Luffy OP01-024 | Super Rare | $12.50 | $25.00 |"), "{}", markdown);
+    assert!(markdown.contains("| 1 | Monkey.D.Luffy OP01-024 (damaged) | Super Rare | $3.75 | $3.75 |"), "{}", markdown);
+}
+
+#[test]
+fn html_reports_embed_the_card_images()
+{
+    common::mock();
+
+    let mut thumbnails = HashMap::new();
+    let (page, webcache) = html::page(&common::db(&[1001, 1002]), HashMap::new(), &common::settings(), &mut thumbnails);
+    let page = page.unwrap();
+
+    assert!(page.contains("Monkey.D.Luffy"));
+    assert!(page.contains("$15.75"));
+    assert!(page.contains("data:image/jpeg;base64,"));
+
+    // 1002 has no picture in the fixtures, which shouldn't stop the report
+    assert!(webcache.contains_key("card_image_b64:1001"));
+    assert!(!webcache.contains_key("card_image_b64:1002"));
+}
+
+#[test]
+fn cdn_reports_link_to_the_image_server()
+{
+    let mock_url = common::mock();
+
+    let mut settings = common::settings();
+    settings.report_mode = ReportMode::Cdn;
+
+    let (page, webcache) = html::page(&common::db(&[1001]), HashMap::new(), &settings, &mut HashMap::new());
+
+    assert!(page.unwrap().contains(&format!("{}/product/1001_400w.jpg", mock_url)));
+    assert!(!webcache.contains_key("card_image_b64:1001"));
+}
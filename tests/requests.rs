@@ -0,0 +1,62 @@
+//! what actually goes out to tcgplayer, kept apart since it points the library at a server that only listens
+
+use std::collections::HashMap;
+use std::sync::mpsc;
+
+use serde_json::{json, Value};
+use tiny_http::{Response, Server};
+
+use onepiececards::util::{self, SearchFilters};
+
+// answers one request with an empty search and hands back its query string and body
+fn capture() -> mpsc::Receiver<(String, Value)>
+{
+    let server = Server::http("127.0.0.1:0").unwrap();
+    std::env::set_var(util::API_URL_VAR, format!("http://{}", server.server_addr().to_ip().unwrap()));
+
+    let (sender, receiver) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        let mut request = server.recv().unwrap();
+
+        let mut body = String::new();
+        request.as_reader().read_to_string(&mut body).unwrap();
+
+        sender.send((request.url().to_string(), serde_json::from_str(&body).unwrap())).unwrap();
+        request.respond(Response::from_string(r#"{"results": [{"totalResults": 0, "results": []}]}"#)).unwrap();
+    });
+
+    receiver
+}
+
+#[test]
+fn search_filters_go_out_as_url_names()
+{
+    let requests = capture();
+
+    let mut filters = SearchFilters::default();
+
+    for (name, value) in [("set", "Romance Dawn"), ("rarity", "Super Rare"), ("color", "Red"), ("type", "Leader"), ("max", "5")]
+    {
+        assert!(util::search_filter(&mut filters, name, value));
+    }
+
+    util::search_page("Luffy & Ace", 1, &filters, HashMap::new());
+
+    let (url, body) = requests.recv().unwrap();
+
+    assert_eq!(url, "/v1/search/request?q=Luffy+%26+Ace");
+    assert_eq!(body["from"], 24);
+    assert_eq!(body["size"], 24);
+    assert_eq!(body["filters"], json!({
+        "term": {
+            "productLineName": ["one-piece-card-game"],
+            "setName": ["romance-dawn"],
+            "rarityName": ["super-rare"],
+            "color": ["red"],
+            "cardType": ["leader"]
+        },
+        "range": { "marketPrice": { "lte": 5.0 } },
+        "match": {}
+    }));
+}
@@ -0,0 +1,66 @@
+//! the dashboard server, started on a free local port
+
+mod common;
+
+use std::net::TcpListener;
+use std::time::Duration;
+
+use onepiececards::server;
+
+// a port nothing is listening on right now
+fn free_port() -> u16
+{
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+// starts the server on a scratch folder and waits until it answers
+fn start(client: &reqwest::blocking::Client) -> u16
+{
+    common::mock();
+
+    let folder = common::scratch("server");
+    let port = free_port();
+    let paths = ["cards.json", "webcache.dat", "journal.jsonl"].map(|name| folder.join(name).to_string_lossy().to_string());
+
+    std::thread::spawn(move || server::serve(server::DEFAULT_ADDRESS, port, common::db(&[1001]), &paths[0], &paths[1], &paths[2]));
+
+    let url = format!("http://127.0.0.1:{}/api/cards", port);
+
+    // the server needs a moment to start listening
+    let response = (0..50)
+        .find_map(|_| client.get(&url).send().ok().or_else(|| { std::thread::sleep(Duration::from_millis(100)); None }))
+        .expect("the server should start");
+
+    assert_eq!(response.status(), 200);
+
+    port
+}
+
+#[test]
+fn only_local_host_names_are_served()
+{
+    let client = reqwest::blocking::Client::new();
+    let port = start(&client);
+
+    let response = client.get(format!("http://127.0.0.1:{}/api/cards", port)).header("Host", format!("localhost:{}", port)).send().unwrap();
+    assert_eq!(response.status(), 200);
+
+    // a rebound dns name reaches the same socket but can't read the page with the session token
+    let response = client.get(format!("http://127.0.0.1:{}/manage", port)).header("Host", format!("attacker.example:{}", port)).send().unwrap();
+    assert_eq!(response.status(), 403);
+}
+
+#[test]
+fn huge_bodies_are_turned_away()
+{
+    let client = reqwest::blocking::Client::new();
+    let port = start(&client);
+
+    let response = client.post(format!("http://127.0.0.1:{}/api/cards", port))
+        .header("Content-Type", "application/json")
+        .body(" ".repeat(1024 * 1024))
+        .send()
+        .unwrap();
+
+    assert_eq!(response.status(), 413);
+}
@@ -0,0 +1,60 @@
+//! collection stats over the fixtures
+
+mod common;
+
+use std::collections::HashMap;
+
+use onepiececards::stats;
+use onepiececards::text::Format;
+
+#[test]
+fn stats_group_and_rank_the_collection()
+{
+    common::mock();
+
+    let (stats, _) = stats::gather(&common::db(&[1001, 1001, 1002, 1003, 1004]), HashMap::new(), &common::settings());
+
+    assert_eq!(stats.card_count, 5);
+    assert_eq!(stats.total, 29.0);
+    assert_eq!(stats.unknown_prices, 1);
+
+    let super_rare = stats.rarities.iter().find(|group| group.name == "Super Rare").unwrap();
+    assert_eq!((super_rare.count, super_rare.value, super_rare.average), (2, 25.0, 12.5));
+
+    let leaders = stats.card_types.iter().find(|group| group.name == "Leader").unwrap();
+    assert_eq!((leaders.count, leaders.value, leaders.average), (2, 0.75, 0.375));
+
+    // most valuable first
+    assert_eq!(stats.colors.iter().map(|group| group.name.as_str()).collect::<Vec<&str>>(), vec!["Red", "Blue", "Purple", "Green"]);
+    assert_eq!(stats.sets[0].name, "Romance Dawn");
+    assert_eq!(stats.sets[0].share, 28.25 / 29.0);
+
+    // leaders have no cost, the gap at 2 still shows
+    let curve: Vec<(u32, u32)> = stats.cost_curve.iter().map(|cost| (cost.cost, cost.count)).collect();
+    assert_eq!(curve, vec![(0, 0), (1, 1), (2, 0), (3, 2)]);
+
+    // copies of a product count together
+    assert_eq!(stats.top[0].name, "Monkey.D.Luffy OP01-024");
+    assert_eq!((stats.top[0].quantity, stats.top[0].value), (2, 25.0));
+    assert_eq!(stats.top.len(), 4);
+    assert_eq!(stats.top_share, 1.0);
+
+    let output = stats::render(&stats, Format::Text);
+
+    assert!(output.contains("Super Rare  2      $25.00  $12.50   86.2%"), "{}", output);
+    assert!(output.contains("3     2      ####################"));
+    assert!(output.contains("Top 10 Cards (100.0% of the value, $29.00)"));
+}
+
+#[test]
+fn empty_collections_have_no_stats()
+{
+    common::mock();
+
+    let (stats, _) = stats::gather(&common::db(&[]), HashMap::new(), &common::settings());
+
+    assert_eq!(stats.total, 0.0);
+    assert!(stats.cost_curve.is_empty());
+    assert!(stats.top.is_empty());
+    assert_eq!(stats.top_share, 0.0);
+}
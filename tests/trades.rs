@@ -0,0 +1,36 @@
+//! pricing and swapping trades between owners, priced by the mock
+
+mod common;
+
+use std::collections::HashMap;
+
+use onepiececards::trade;
+use onepiececards::valuation::{Condition, PriceBasis};
+
+#[test]
+fn trades_are_valued_at_the_basis_and_condition()
+{
+    common::mock();
+
+    let mut db = common::db(&[]);
+    db.owners = vec!["Ann".to_string(), "Ben".to_string()];
+
+    let mut luffy = common::card(1001, Condition::Damaged);
+    luffy.owner = "Ann".to_string();
+    let mut nami = common::card(1002, Condition::NearMint);
+    nami.owner = "Ben".to_string();
+    db.cards = vec![luffy, nami];
+
+    let mut settings = common::settings();
+    settings.price_basis = PriceBasis::Low;
+
+    let (proposal, _) = trade::propose(&db, "Ann", "Ben", &[0], &[1], &settings, HashMap::new());
+    let proposal = proposal.unwrap();
+
+    assert_eq!(proposal.first_gives[0].value, 3.0);
+    assert_eq!(trade::total(&proposal.first_gives), 3.0);
+
+    assert!(trade::execute(&mut db, proposal, &[0], &[1]));
+    assert_eq!(db.cards[0].owner, "Ben");
+    assert_eq!(db.trades.len(), 1);
+}